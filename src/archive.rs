@@ -0,0 +1,335 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use eyre::{eyre, Result};
+use lazy_static::lazy_static;
+use log::{debug, error, info};
+use regex::{Captures, Regex};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use url::Url;
+
+use crate::{http, new_file_path, vault_folder};
+
+lazy_static! {
+    static ref SCRIPT_RE: Regex = Regex::new(r"(?is)<script\b.*?</script\s*>").expect("Invalid script regex");
+    static ref TAG_RE: Regex = Regex::new(r"(?s)<[a-zA-Z][^>]*>").expect("Invalid tag regex");
+    static ref EVENT_ATTR_RE: Regex =
+        Regex::new(r#"(?is)\son[a-z]+\s*=\s*(?:"[^"]*"|'[^']*'|[^\s>]+)"#).expect("Invalid event attribute regex");
+    static ref JAVASCRIPT_URL_RE: Regex = Regex::new(
+        r#"(?is)\s(?:href|src|action|formaction|xlink:href)\s*=\s*(?:"\s*javascript:[^"]*"|'\s*javascript:[^']*'|javascript:[^\s>]*)"#
+    )
+    .expect("Invalid javascript URL regex");
+    static ref LINK_RE: Regex = Regex::new(r"(?is)<link\b[^>]*>").expect("Invalid link regex");
+    static ref IMG_RE: Regex = Regex::new(r"(?is)<img\b[^>]*>").expect("Invalid img regex");
+    static ref SRCSET_RE: Regex = Regex::new(r#"(?is)\s(?:srcset|sizes|loading)\s*=\s*(?:"[^"]*"|'[^']*'|[^\s>]+)"#)
+        .expect("Invalid srcset regex");
+    static ref CSS_URL_RE: Regex =
+        Regex::new(r#"(?i)url\(\s*(?:"([^"]*)"|'([^']*)'|([^)\s]*))\s*\)"#).expect("Invalid CSS url regex");
+    static ref HEAD_RE: Regex = Regex::new(r"(?i)<head\b[^>]*>").expect("Invalid head regex");
+    static ref ATTR_RE: Regex = Regex::new(r#"(?is)\s([^\s"'<>/=]+)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))"#).expect("Invalid attr regex");
+    static ref STYLE_RE: Regex = Regex::new(r"(?is)(<style\b[^>]*>)(.*?)(</style\s*>)").expect("Invalid style regex");
+    static ref STYLE_ATTR_RE: Regex =
+        Regex::new(r#"(?is)\sstyle\s*=\s*(?:"([^"]*)"|'([^']*)')"#).expect("Invalid style attribute regex");
+}
+
+/// Caps how many subresources, stylesheets included, a single snapshot will fetch.
+const MAX_RESOURCES: usize = 200;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ArchiveOptions {
+    #[serde(default = "default_folder")]
+    pub folder: String,
+}
+
+fn default_folder() -> String {
+    "archive".to_string()
+}
+
+fn find_attr<'t>(tag: &'t str, name: &str) -> Option<Captures<'t>> {
+    ATTR_RE.captures_iter(tag).find(|caps| caps[1].eq_ignore_ascii_case(name))
+}
+
+fn attr(tag: &str, name: &str) -> Option<String> {
+    let caps = find_attr(tag, name)?;
+    caps.get(2)
+        .or_else(|| caps.get(3))
+        .or_else(|| caps.get(4))
+        .map(|m| m.as_str().replace("&amp;", "&"))
+}
+
+fn set_attr(tag: &str, name: &str, value: &str) -> String {
+    match find_attr(tag, name) {
+        Some(caps) => {
+            let whole = caps.get(0).expect("capture 0 always exists");
+            let value_start = [2, 3, 4].iter().find_map(|i| caps.get(*i)).map_or(whole.end(), |m| m.start());
+            // Keep everything up to the value, minus its opening quote.
+            let prefix = tag[whole.start()..value_start].trim_end_matches(['"', '\'']);
+            format!("{}{}\"{}\"{}", &tag[..whole.start()], prefix, value, &tag[whole.end()..])
+        }
+        None => tag.to_string(),
+    }
+}
+
+struct Snapshot {
+    resources: HashMap<String, Option<String>>,
+    fetched: usize,
+}
+
+impl Snapshot {
+    /// Every network fetch goes through here so the whole snapshot stays within `MAX_RESOURCES`.
+    async fn fetch(&mut self, url: &str) -> Option<(String, Vec<u8>)> {
+        if self.fetched >= MAX_RESOURCES {
+            debug!("Skipping {}: snapshot resource limit reached", url);
+            return None;
+        }
+        self.fetched += 1;
+        match fetch_bytes(url).await {
+            Ok(fetched) => Some(fetched),
+            Err(e) => {
+                error!("Failed to archive resource {}: {:?}", url, e);
+                None
+            }
+        }
+    }
+
+    async fn data_uri(&mut self, url: &str) -> Option<String> {
+        if url.starts_with("data:") {
+            return Some(url.to_string());
+        }
+        if let Some(cached) = self.resources.get(url) {
+            return cached.clone();
+        }
+        let result = self
+            .fetch(url)
+            .await
+            .map(|(content_type, bytes)| format!("data:{};base64,{}", content_type, STANDARD.encode(bytes)));
+        self.resources.insert(url.to_string(), result.clone());
+        result
+    }
+
+    async fn inline_css_urls(&mut self, css: &str, base: &Url) -> String {
+        let mut inlined = String::with_capacity(css.len());
+        let mut last = 0;
+        for caps in CSS_URL_RE.captures_iter(css) {
+            let whole = caps.get(0).expect("capture 0 always exists");
+            let reference = caps
+                .get(1)
+                .or_else(|| caps.get(2))
+                .or_else(|| caps.get(3))
+                .map(|m| m.as_str())
+                .unwrap_or_default();
+            inlined.push_str(&css[last..whole.start()]);
+            match base.join(reference).ok() {
+                Some(resolved) if !reference.is_empty() && !reference.starts_with('#') => {
+                    match self.data_uri(resolved.as_str()).await {
+                        Some(data_uri) => inlined.push_str(&format!("url(\"{data_uri}\")")),
+                        None => inlined.push_str(&format!("url(\"{resolved}\")")),
+                    }
+                }
+                _ => inlined.push_str(whole.as_str()),
+            }
+            last = whole.end();
+        }
+        inlined.push_str(&css[last..]);
+        inlined
+    }
+
+    /// Inlines the references in the page's own `<style>` blocks and `style` attributes.
+    async fn inline_page_styles(&mut self, html: &str, base: &Url) -> String {
+        let mut out = String::with_capacity(html.len());
+        let mut last = 0;
+        for caps in STYLE_RE.captures_iter(html) {
+            let whole = caps.get(0).expect("capture 0 always exists");
+            out.push_str(&html[last..whole.start()]);
+            last = whole.end();
+            let css = self.inline_css_urls(&caps[2], base).await;
+            out.push_str(&format!("{}{}{}", &caps[1], css, &caps[3]));
+        }
+        out.push_str(&html[last..]);
+
+        let html = out;
+        let mut out = String::with_capacity(html.len());
+        let mut last = 0;
+        for caps in STYLE_ATTR_RE.captures_iter(&html) {
+            let whole = caps.get(0).expect("capture 0 always exists");
+            out.push_str(&html[last..whole.start()]);
+            last = whole.end();
+            let css = caps.get(1).or_else(|| caps.get(2)).map(|m| m.as_str()).unwrap_or_default();
+            if !CSS_URL_RE.is_match(css) {
+                out.push_str(whole.as_str());
+                continue;
+            }
+            let css = css.replace("&quot;", "\"").replace("&amp;", "&");
+            let css = self.inline_css_urls(&css, base).await;
+            out.push_str(&format!(" style=\"{}\"", css.replace('&', "&amp;").replace('"', "&quot;")));
+        }
+        out.push_str(&html[last..]);
+        out
+    }
+}
+
+async fn fetch_bytes(url: &str) -> Result<(String, Vec<u8>)> {
+    let response = http::get(url).await?;
+    if !response.status().is_success() {
+        return Err(eyre!("Request to {} failed with status {}", url, response.status()));
+    }
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.split(';').next().unwrap_or_default().trim().to_string())
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| "application/octet-stream".to_string());
+    Ok((content_type, http::bytes(response).await?))
+}
+
+/// Removes `<script>` elements, `on*=` handlers and `javascript:` URLs.
+fn strip_scripts(html: &str) -> String {
+    let html = SCRIPT_RE.replace_all(html, "");
+    TAG_RE
+        .replace_all(&html, |caps: &Captures| {
+            let tag = EVENT_ATTR_RE.replace_all(&caps[0], "");
+            JAVASCRIPT_URL_RE.replace_all(&tag, "").into_owned()
+        })
+        .into_owned()
+}
+
+/// Produces a single self-contained HTML file: scripts are dropped, stylesheets
+/// are inlined, and images and CSS references become data URIs.
+pub async fn snapshot(content: &str, url: &str) -> Result<String> {
+    let base = Url::parse(url).map_err(|e| eyre!("Failed to parse URL: {}", e))?;
+    let mut snapshot = Snapshot {
+        resources: HashMap::new(),
+        fetched: 0,
+    };
+    let html = strip_scripts(content);
+    let html = snapshot.inline_page_styles(&html, &base).await;
+
+    let mut out = String::with_capacity(html.len());
+    let mut last = 0;
+    for tag in LINK_RE.find_iter(&html) {
+        out.push_str(&html[last..tag.start()]);
+        last = tag.end();
+        let is_stylesheet = attr(tag.as_str(), "rel").is_some_and(|rel| rel.to_lowercase().contains("stylesheet"));
+        let href = attr(tag.as_str(), "href").and_then(|href| base.join(&href).ok());
+        match (is_stylesheet, href) {
+            (true, Some(href)) => match snapshot.fetch(href.as_str()).await {
+                Some((_, bytes)) => {
+                    let css = String::from_utf8_lossy(&bytes);
+                    let css = snapshot.inline_css_urls(&css, &href).await;
+                    out.push_str(&format!("<style>\n{css}\n</style>"));
+                }
+                None => out.push_str(tag.as_str()),
+            },
+            // Preloads and prefetches would point at the live site; drop them.
+            (false, _) if attr(tag.as_str(), "rel").is_some_and(|rel| rel.contains("preload") || rel.contains("prefetch")) => {}
+            _ => out.push_str(tag.as_str()),
+        }
+    }
+    out.push_str(&html[last..]);
+
+    let html = out;
+    let mut out = String::with_capacity(html.len());
+    let mut last = 0;
+    for tag in IMG_RE.find_iter(&html) {
+        out.push_str(&html[last..tag.start()]);
+        last = tag.end();
+        let src = attr(tag.as_str(), "src").or_else(|| attr(tag.as_str(), "data-src"));
+        let resolved = src.and_then(|src| base.join(&src).ok());
+        let data_uri = match &resolved {
+            Some(resolved) => snapshot.data_uri(resolved.as_str()).await,
+            None => None,
+        };
+        match data_uri {
+            Some(data_uri) if attr(tag.as_str(), "src").is_some() => {
+                let tag = SRCSET_RE.replace_all(tag.as_str(), "");
+                out.push_str(&set_attr(&tag, "src", &data_uri));
+            }
+            Some(data_uri) => {
+                let tag = SRCSET_RE.replace_all(tag.as_str(), "");
+                out.push_str(&tag.replacen("<img", &format!("<img src=\"{data_uri}\""), 1));
+            }
+            None => out.push_str(tag.as_str()),
+        }
+    }
+    out.push_str(&html[last..]);
+
+    // Keep in-page links pointing at the original site.
+    let base_tag = format!("<base href=\"{base}\">");
+    let out = match HEAD_RE.find(&out) {
+        Some(head) => format!("{}{}{}", &out[..head.end()], base_tag, &out[head.end()..]),
+        None => format!("{base_tag}{out}"),
+    };
+    debug!("snapshot: url={} resources={}", url, snapshot.resources.len());
+    Ok(out)
+}
+
+/// Archives the page into the vault and returns the wikilink to store in frontmatter.
+pub async fn archive_page(
+    content: &str,
+    url: &str,
+    title: &str,
+    vault_path: &Path,
+    options: &ArchiveOptions,
+) -> Result<String> {
+    let html = snapshot(content, url).await?;
+    let folder_path = vault_folder(vault_path, Some(options.folder.clone()))?;
+    // Titles like "Home" repeat across sites; number the file rather than replace another page's archive.
+    let file_path = new_file_path(&folder_path, title, "html")?;
+    info!("archive_page: url={} file_path={:?}", url, file_path);
+    std::fs::File::options()
+        .write(true)
+        .create_new(true)
+        .open(&file_path)
+        .and_then(|mut file| std::io::Write::write_all(&mut file, html.as_bytes()))
+        .map_err(|e| eyre!("Failed to write archive: {:?} with error {}", file_path, e))?;
+    let file_name = file_path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    Ok(format!("[[{file_name}]]"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_snapshot_without_fetches() -> Result<()> {
+        let html = r#"<html><head><title>T</title><script src="/app.js"></script>
+            <link rel="preload" href="/font.woff2"><link rel="icon" href="/favicon.ico"></head>
+            <body onload="track()"><img src="data:image/png;base64,AAAA" srcset="a.png 2x" alt="x"><script>alert(1)</script>
+            <a href="javascript:void(0)" onclick='go()'>menu</a></body></html>"#;
+        let archived = snapshot(html, "https://example.com/post").await?;
+        assert!(archived.contains(r#"<head><base href="https://example.com/post"><title>T</title>"#));
+        assert!(!archived.contains("<script"));
+        assert!(!archived.contains("track()") && !archived.contains("go()") && !archived.contains("javascript:"));
+        assert!(archived.contains("<body><img") && archived.contains("<a>menu</a>"));
+        assert!(!archived.contains("preload"));
+        assert!(archived.contains(r#"<link rel="icon" href="/favicon.ico">"#));
+        assert!(archived.contains(r#"<img src="data:image/png;base64,AAAA" alt="x">"#));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_page_styles_and_resource_limit() -> Result<()> {
+        let html = r#"<style>.a{background:url('data:image/png;base64,AAAA')}</style><div style="mask: url(&quot;#m&quot;); background: url('data:image/gif;base64,BBBB')">"#;
+        let archived = snapshot(html, "https://example.com/post").await?;
+        assert!(archived.contains(r#"<style>.a{background:url("data:image/png;base64,AAAA")}</style>"#));
+        assert!(archived.contains(r#"<div style="mask: url(&quot;#m&quot;); background: url(&quot;data:image/gif;base64,BBBB&quot;)">"#));
+
+        let mut snapshot = Snapshot {
+            resources: HashMap::new(),
+            fetched: MAX_RESOURCES,
+        };
+        assert!(snapshot.fetch("https://example.invalid/style.css").await.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_attr() {
+        let tag = r#"<img class=hero SRC='a.png?x=1&amp;y=2' alt="A">"#;
+        assert_eq!(attr(tag, "src"), Some("a.png?x=1&y=2".to_string()));
+        assert_eq!(attr(tag, "class"), Some("hero".to_string()));
+        assert_eq!(attr(tag, "srcset"), None);
+        assert_eq!(set_attr(tag, "alt", "B"), r#"<img class=hero SRC='a.png?x=1&amp;y=2' alt="B">"#);
+    }
+}
@@ -0,0 +1,136 @@
+use eyre::{eyre, Result};
+use log::{debug, info};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::{download, http, vault_folder, Config};
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Attachments {
+    #[serde(default)]
+    pub download: bool,
+    #[serde(default = "default_folder")]
+    pub folder: String,
+}
+
+impl Default for Attachments {
+    fn default() -> Self {
+        Attachments {
+            download: false,
+            folder: default_folder(),
+        }
+    }
+}
+
+fn default_folder() -> String {
+    "attachments".to_string()
+}
+
+fn extension(content_type: &str, url: &str) -> String {
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+    let from_mime = match mime {
+        "image/jpeg" | "image/jpg" => Some("jpg"),
+        "image/png" => Some("png"),
+        "image/gif" => Some("gif"),
+        "image/webp" => Some("webp"),
+        "image/avif" => Some("avif"),
+        "image/svg+xml" => Some("svg"),
+        "application/pdf" => Some("pdf"),
+        _ => None,
+    };
+    if let Some(ext) = from_mime {
+        return ext.to_string();
+    }
+
+    Url::parse(url)
+        .ok()
+        .and_then(|u| {
+            u.path_segments()
+                .and_then(|mut segments| segments.next_back().map(str::to_string))
+        })
+        .and_then(|name| name.rsplit_once('.').map(|(_, ext)| ext.to_lowercase()))
+        .filter(|ext| !ext.is_empty() && ext.len() <= 4 && ext.chars().all(|c| c.is_ascii_alphanumeric()))
+        .unwrap_or_else(|| "jpg".to_string())
+}
+
+/// Names the file after its content hash so the same image bookmarked twice
+/// is only stored once.
+fn file_name(bytes: &[u8], ext: &str) -> String {
+    let hash = format!("{:x}", Sha256::digest(bytes));
+    format!("{}.{}", &hash[..16], ext)
+}
+
+/// The content type to store a download under, or `None` when it isn't an image: error pages
+/// and login walls are often served with 200.
+fn image_content_type(content_type: &str, bytes: &[u8]) -> Option<String> {
+    if content_type.trim_start().to_ascii_lowercase().starts_with("image/") {
+        return Some(content_type.to_string());
+    }
+    download::image_magic(bytes).map(str::to_string)
+}
+
+/// Downloads the image at `url` into the attachments folder and returns the stored file name.
+pub async fn download(url: &str, config: &Config) -> Result<String> {
+    debug!("download: url={}", url);
+    let response = http::get(url).await?;
+    if !response.status().is_success() {
+        return Err(eyre!("Failed to download {} with status {}", url, response.status()));
+    }
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    let bytes = http::bytes(response).await?;
+    let content_type = image_content_type(&content_type, &bytes)
+        .ok_or_else(|| eyre!("{} is not an image (content type {:?})", url, content_type))?;
+    store(&bytes, &content_type, url, config)
+}
+
+/// Writes already-fetched bytes into the attachments folder and returns the stored file name.
+pub fn store(bytes: &[u8], content_type: &str, url: &str, config: &Config) -> Result<String> {
+    let name = file_name(bytes, &extension(content_type, url));
+    let folder_path = vault_folder(&config.vault, Some(config.attachments.folder.clone()))?;
+    let file_path = folder_path.join(&name);
+    if file_path.exists() {
+        debug!("attachment already stored: {:?}", file_path);
+    } else {
+        info!("saving attachment {} to {:?}", url, file_path);
+        std::fs::write(&file_path, bytes)
+            .map_err(|e| eyre!("Failed to write attachment: {:?} with error {}", file_path, e))?;
+    }
+    Ok(name)
+}
+
+pub fn generate_embed_code(file_name: &str, width: usize, height: usize) -> String {
+    format!("![[{file_name}|{width}x{height}]]")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_name_is_content_addressed() {
+        let first = file_name(b"image bytes", "png");
+        assert_eq!(first, file_name(b"image bytes", "png"));
+        assert_ne!(first, file_name(b"other bytes", "png"));
+        assert_eq!(first.len(), "0123456789abcdef.png".len());
+    }
+
+    #[test]
+    fn test_extension() {
+        assert_eq!(extension("image/png; charset=binary", "https://x.com/a"), "png");
+        assert_eq!(extension("application/octet-stream", "https://x.com/a/b.WEBP?w=10"), "webp");
+        assert_eq!(extension("", "https://i.ytimg.com/vi/abc/hqdefault"), "jpg");
+    }
+
+    #[test]
+    fn test_image_content_type() {
+        assert_eq!(image_content_type("image/png", b"").as_deref(), Some("image/png"));
+        assert_eq!(image_content_type("application/octet-stream", b"GIF89a...").as_deref(), Some("image/gif"));
+        assert_eq!(image_content_type("text/html; charset=utf-8", b"<!doctype html><title>Sign in</title>"), None);
+    }
+}
@@ -0,0 +1,123 @@
+use eyre::Result;
+use lazy_static::lazy_static;
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+
+use crate::wikilink;
+
+lazy_static! {
+    static ref BYLINE_PREFIX_RE: Regex =
+        Regex::new(r"(?i)^(?:(?:written|posted|published|reported)\s+)?by[:\s]+").expect("Invalid byline regex");
+    static ref SEPARATOR_RE: Regex =
+        Regex::new(r"(?i)\s*(?:,|;|\||·|•|\n|\s&\s|\sand\s)\s*").expect("Invalid author separator regex");
+    static ref LAST_FIRST_RE: Regex = Regex::new(r"^[^\s,;|]+,\s*[^\s,;|]+$").expect("Invalid last-first name regex");
+}
+
+/// Longer fragments are sentences or bios, not names.
+const MAX_NAME_WORDS: usize = 5;
+
+fn is_name(candidate: &str) -> bool {
+    let words = candidate.split_whitespace().count();
+    (1..=MAX_NAME_WORDS).contains(&words)
+        && !candidate.chars().any(|c| c.is_ascii_digit())
+        && !candidate.contains("://")
+        && !candidate.starts_with('@')
+        && !candidate.eq_ignore_ascii_case("not specified")
+}
+
+/// Splits bylines like "By Jane Doe and John Roe | June 5, 2023" into names,
+/// dropping dates, URLs and handles, and removing duplicates.
+pub fn clean<I, S>(candidates: I) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut authors: Vec<String> = Vec::new();
+    for candidate in candidates {
+        let candidate = candidate.as_ref().split_whitespace().collect::<Vec<_>>().join(" ");
+        let candidate = BYLINE_PREFIX_RE.replace(&candidate, "");
+        // "Doe, Jane" is one person written surname first, not two authors.
+        let parts: Vec<&str> = if LAST_FIRST_RE.is_match(&candidate) {
+            vec![&candidate]
+        } else {
+            SEPARATOR_RE.split(&candidate).collect()
+        };
+        for part in parts {
+            let part = part.split_whitespace().collect::<Vec<_>>().join(" ");
+            let part = BYLINE_PREFIX_RE.replace(&part, "").trim().to_string();
+            if is_name(&part) && !authors.iter().any(|author| author.eq_ignore_ascii_case(&part)) {
+                authors.push(part);
+            }
+        }
+    }
+    authors
+}
+
+fn element_text(element: ElementRef) -> String {
+    let name_selector = Selector::parse("[itemprop='name']").expect("Invalid name selector");
+    match element.select(&name_selector).next() {
+        Some(name) => name.text().collect::<Vec<_>>().join(" "),
+        None => element.value().attr("content").map_or_else(
+            || element.text().collect::<Vec<_>>().join("\n"),
+            str::to_string,
+        ),
+    }
+}
+
+/// Authors from page markup, taking the first source that yields any names.
+pub fn extract(document: &Html) -> Vec<String> {
+    let sources = [
+        "meta[name='author']",
+        "meta[property='article:author']",
+        "[rel~='author']",
+        "[itemprop='author']",
+        ".author",
+    ];
+    for source in sources {
+        let selector = Selector::parse(source).expect("Invalid author selector");
+        let authors = clean(document.select(&selector).map(element_text));
+        if !authors.is_empty() {
+            return authors;
+        }
+    }
+    Vec::new()
+}
+
+pub fn wikilinks(authors: &[String]) -> Result<Vec<String>> {
+    authors
+        .iter()
+        .map(|author| if author.starts_with("[[") { Ok(author.clone()) } else { wikilink(author) })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_authors() {
+        let authors = clean([
+            "By Jane Doe and John Roe | June 5, 2023",
+            "jane doe",
+            "https://example.com/authors/jane",
+            "Not specified",
+            "Written by: Ana Lee, Bo Kim",
+            "By Roe, Richard",
+        ]);
+        assert_eq!(authors, vec!["Jane Doe", "John Roe", "Ana Lee", "Bo Kim", "Roe, Richard"]);
+    }
+
+    #[test]
+    fn test_extract_authors() -> Result<()> {
+        let html = r#"<html><head>
+            <meta property="article:author" content="https://facebook.com/someone">
+            </head><body>
+            <div class="author">By <a rel="author" href="/jane">Jane Doe</a> · 5 min read</div>
+            <a rel="author" href="/john">John Roe</a>
+            </body></html>"#;
+        let authors = extract(&Html::parse_document(html));
+        assert_eq!(authors, vec!["Jane Doe", "John Roe"]);
+        assert_eq!(wikilinks(&authors)?, vec!["[[Jane Doe]]", "[[John Roe]]"]);
+        Ok(())
+    }
+}
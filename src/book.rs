@@ -0,0 +1,268 @@
+use eyre::{eyre, Result};
+use lazy_static::lazy_static;
+use log::debug;
+use regex::Regex;
+use scraper::{Html, Selector};
+use serde::Deserialize;
+use url::Url;
+
+use crate::{http, jsonld, Capture};
+
+lazy_static! {
+    static ref ISBN_IN_URL_RE: Regex = Regex::new(r"(?:^|[/=_-])(97[89]\d{10})(?:$|[/?&#._-])").expect("Invalid ISBN URL regex");
+    static ref ISBN_IN_TEXT_RE: Regex =
+        Regex::new(r"(?i)ISBN(?:-1[03])?\s*:?\s*((?:97[89][\s-]?)?\d[\d\s-]{7,14}[\dX])").expect("Invalid ISBN text regex");
+    static ref YEAR_RE: Regex = Regex::new(r"\b\d{4}\b").expect("Invalid year regex");
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BookOptions {
+    /// Initial value of the `status` field on new book notes.
+    #[serde(default = "default_status")]
+    pub status: String,
+}
+
+fn default_status() -> String {
+    "to-read".to_string()
+}
+
+impl Default for BookOptions {
+    fn default() -> Self {
+        BookOptions { status: default_status() }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum BookUrl {
+    Isbn(String),
+    /// A store or catalogue page that has to be fetched to find the ISBN.
+    Page(String),
+}
+
+#[derive(Debug, Default)]
+pub struct Book {
+    pub isbn: String,
+    pub title: String,
+    pub authors: Vec<String>,
+    pub publishers: Vec<String>,
+    pub pages: Option<u64>,
+    pub publish_date: String,
+    pub subjects: Vec<String>,
+    pub cover: String,
+    pub url: String,
+}
+
+/// Validates an ISBN-10 or ISBN-13, ignoring hyphens and spaces, and returns it as an ISBN-13.
+pub fn normalize_isbn(raw: &str) -> Option<String> {
+    let isbn: String = raw.chars().filter(|c| !c.is_whitespace() && *c != '-').collect::<String>().to_uppercase();
+    let digits = |s: &str| s.chars().filter_map(|c| c.to_digit(10)).collect::<Vec<_>>();
+    let isbn13_check = |d: &[u32]| (10 - d.iter().enumerate().map(|(i, d)| d * if i % 2 == 0 { 1 } else { 3 }).sum::<u32>() % 10) % 10;
+    match isbn.len() {
+        13 if isbn.chars().all(|c| c.is_ascii_digit()) => {
+            let d = digits(&isbn);
+            (isbn13_check(&d[..12]) == d[12]).then_some(isbn)
+        }
+        10 if isbn[..9].chars().all(|c| c.is_ascii_digit()) => {
+            let check = match isbn.chars().last()? {
+                'X' => 10,
+                c => c.to_digit(10)?,
+            };
+            let d = digits(&isbn[..9]);
+            let sum: u32 = d.iter().enumerate().map(|(i, d)| d * (10 - i as u32)).sum::<u32>() + check;
+            if !sum.is_multiple_of(11) {
+                return None;
+            }
+            let mut d13 = vec![9, 7, 8];
+            d13.extend(d);
+            let check = isbn13_check(&d13);
+            Some(format!("978{}{}", &isbn[..9], check))
+        }
+        _ => None,
+    }
+}
+
+pub fn parse_url(url: &str) -> Option<BookUrl> {
+    let parsed = Url::parse(url).ok()?;
+    let host = parsed.host_str()?.trim_start_matches("www.");
+    let segments: Vec<&str> = parsed.path_segments()?.filter(|s| !s.is_empty()).collect();
+
+    if host == "openlibrary.org" {
+        return match segments.as_slice() {
+            ["isbn", isbn] => normalize_isbn(isbn).map(BookUrl::Isbn),
+            _ => None,
+        };
+    }
+    if host.starts_with("amazon.") || host.contains(".amazon.") {
+        // Print editions use their ISBN-10 as the ASIN; anything else (Kindle editions, non-book
+        // products) is left to the default handler.
+        let asin = segments.windows(2).find(|pair| pair[0] == "dp" || pair[0] == "product").map(|pair| pair[1])?;
+        return normalize_isbn(asin).map(BookUrl::Isbn);
+    }
+    if host == "goodreads.com" {
+        return matches!(segments.as_slice(), ["book", "show", _, ..]).then(|| BookUrl::Page(url.to_string()));
+    }
+    ISBN_IN_URL_RE
+        .captures(parsed.path())
+        .and_then(|caps| normalize_isbn(&caps[1]))
+        .map(BookUrl::Isbn)
+}
+
+/// The ISBN a book page declares about itself, from `book:isbn` meta tags or a JSON-LD `Book` among `entities`.
+pub fn isbn_from_document(document: &Html, entities: &[serde_json::Value]) -> Option<String> {
+    let selector = Selector::parse("meta[property='books:isbn'], meta[property='book:isbn']").expect("Invalid ISBN meta selector");
+    let meta = document.select(&selector).filter_map(|meta| meta.value().attr("content"));
+    let book = jsonld::find_entity(entities, "Book");
+    let from_json_ld = book.iter().flat_map(|book| {
+        // Goodreads hangs the ISBN off a `workExample` edition.
+        let editions = book["workExample"].as_array().cloned().unwrap_or_else(|| vec![book["workExample"].clone()]);
+        std::iter::once(book["isbn"].clone())
+            .chain(editions.into_iter().map(|edition| edition["isbn"].clone()))
+            .filter_map(|isbn| isbn.as_str().map(str::to_string))
+            .collect::<Vec<_>>()
+    });
+    meta.map(str::to_string).chain(from_json_ld).find_map(|isbn| normalize_isbn(&isbn))
+}
+
+fn isbn_from_text(text: &str) -> Option<String> {
+    ISBN_IN_TEXT_RE.captures_iter(text).find_map(|caps| normalize_isbn(&caps[1]))
+}
+
+fn names(value: &serde_json::Value) -> Vec<String> {
+    value
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|item| item["name"].as_str().map(str::to_string))
+        .collect()
+}
+
+fn parse_open_library(response: &serde_json::Value, isbn: &str) -> Result<Book> {
+    let record = &response[format!("ISBN:{isbn}")];
+    if record.is_null() {
+        return Err(eyre!("Open Library has no record for ISBN {}", isbn));
+    }
+    let title = record["title"].as_str().unwrap_or_default();
+    let title = match record["subtitle"].as_str() {
+        Some(subtitle) => format!("{title}: {subtitle}"),
+        None => title.to_string(),
+    };
+    Ok(Book {
+        isbn: isbn.to_string(),
+        title,
+        authors: names(&record["authors"]),
+        publishers: names(&record["publishers"]),
+        pages: record["number_of_pages"].as_u64(),
+        publish_date: record["publish_date"].as_str().unwrap_or_default().to_string(),
+        subjects: names(&record["subjects"]),
+        cover: ["large", "medium", "small"]
+            .iter()
+            .find_map(|size| record["cover"][size].as_str())
+            .unwrap_or_default()
+            .to_string(),
+        url: record["url"].as_str().unwrap_or_default().to_string(),
+    })
+}
+
+pub async fn fetch(book_url: &BookUrl) -> Result<Book> {
+    debug!("fetch: book_url={:?}", book_url);
+    let isbn = match book_url {
+        BookUrl::Isbn(isbn) => isbn.clone(),
+        BookUrl::Page(url) => {
+            let response = http::get(url).await?;
+            if !response.status().is_success() {
+                return Err(eyre!("Request to {} failed with status {}", url, response.status()));
+            }
+            let content = http::text(response).await?;
+            let document = Html::parse_document(&content);
+            isbn_from_document(&document, &jsonld::entities(&document))
+                .or_else(|| isbn_from_text(&document.root_element().text().collect::<Vec<_>>().join(" ")))
+                .ok_or_else(|| eyre!("No ISBN found on {}", url))?
+        }
+    };
+
+    let api_url = format!("https://openlibrary.org/api/books?bibkeys=ISBN:{isbn}&format=json&jscmd=data");
+    parse_open_library(&http::get_json(&api_url).await?, &isbn)
+}
+
+pub fn capture(book: &Book, options: &BookOptions) -> Capture {
+    let mut details = vec![format!("- **ISBN:** {}", book.isbn)];
+    if !book.publishers.is_empty() {
+        details.push(format!("- **Publisher:** {}", book.publishers.join(", ")));
+    }
+    if !book.publish_date.is_empty() {
+        details.push(format!("- **Published:** {}", book.publish_date));
+    }
+    if let Some(pages) = book.pages {
+        details.push(format!("- **Pages:** {pages}"));
+    }
+    if !book.url.is_empty() {
+        details.push(format!("- **Open Library:** {}", book.url));
+    }
+
+    let mut extra = vec![("isbn".to_string(), book.isbn.clone())];
+    if let Some(publisher) = book.publishers.first() {
+        extra.push(("publisher".to_string(), publisher.clone()));
+    }
+    if let Some(pages) = book.pages {
+        extra.push(("pages".to_string(), pages.to_string()));
+    }
+    // Open Library dates are free text ("1995", "March 5, 2002"); the year is the part worth sorting on.
+    if let Some(year) = YEAR_RE.find(&book.publish_date) {
+        extra.push(("year".to_string(), year.as_str().to_string()));
+    }
+    extra.push(("status".to_string(), options.status.clone()));
+
+    Capture {
+        title: book.title.clone(),
+        description: details.join("\n"),
+        authors: book.authors.clone(),
+        tags: book.subjects.iter().take(10).cloned().collect(),
+        extra,
+        note_type: "book".to_string(),
+        ..Capture::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_book_url() {
+        let cases = [
+            ("https://www.amazon.com/Rust-Programming-Language-2nd/dp/1718503105/ref=sr_1_1", Some(BookUrl::Isbn("9781718503106".into()))),
+            ("https://www.amazon.co.uk/gp/product/B0B7QTMYYZ", None),
+            ("https://www.goodreads.com/book/show/5907.The_Hobbit", Some(BookUrl::Page("https://www.goodreads.com/book/show/5907.The_Hobbit".into()))),
+            ("https://openlibrary.org/isbn/0-261-10221-4", Some(BookUrl::Isbn("9780261102217".into()))),
+            ("https://nostarch.com/books/9781718503106", Some(BookUrl::Isbn("9781718503106".into()))),
+            ("https://nostarch.com/books/9781718503107", None),
+            ("https://www.goodreads.com/author/show/656983", None),
+        ];
+        for (url, expected) in cases {
+            assert_eq!(parse_url(url), expected, "url={url}");
+        }
+    }
+
+    #[test]
+    fn test_book_capture() -> Result<()> {
+        let response = json!({"ISBN:9780261102217": {"title": "The Hobbit", "subtitle": "or There and Back Again",
+            "authors": [{"name": "J.R.R. Tolkien"}], "publishers": [{"name": "HarperCollins"}], "number_of_pages": 310,
+            "publish_date": "March 5, 1999", "subjects": [{"name": "Fantasy"}], "cover": {"large": "https://covers.openlibrary.org/b/id/1-L.jpg"},
+            "url": "https://openlibrary.org/books/OL1M/The_Hobbit"}});
+        let book = parse_open_library(&response, "9780261102217")?;
+        let capture = capture(&book, &BookOptions::default());
+        assert_eq!(capture.title, "The Hobbit: or There and Back Again");
+        assert_eq!(capture.note_type, "book");
+        assert_eq!(book.cover, "https://covers.openlibrary.org/b/id/1-L.jpg");
+        assert!(capture.extra.contains(&("year".to_string(), "1999".to_string())));
+        assert!(capture.extra.contains(&("status".to_string(), "to-read".to_string())));
+
+        let document = Html::parse_document(
+            r#"<script type="application/ld+json">{"@type": "Book", "name": "The Hobbit", "isbn": "0261102214"}</script>"#,
+        );
+        assert_eq!(isbn_from_document(&document, &jsonld::entities(&document)).as_deref(), Some("9780261102217"));
+        assert_eq!(isbn_from_text("Publisher: X; ISBN-13 : 978-0261102217").as_deref(), Some("9780261102217"));
+        Ok(())
+    }
+}
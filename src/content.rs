@@ -0,0 +1,453 @@
+use ego_tree::NodeRef;
+use eyre::{eyre, Result};
+use lazy_static::lazy_static;
+use log::debug;
+use regex::Regex;
+use scraper::{ElementRef, Html, Node, Selector};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use url::Url;
+
+use crate::{new_note_path, quote_yaml, sanitize_filename, vault_folder};
+
+lazy_static! {
+    static ref WHITESPACE_RE: Regex = Regex::new(r"\s+").expect("Invalid whitespace regex");
+    static ref BLANK_LINES_RE: Regex = Regex::new(r"\n{3,}").expect("Invalid blank lines regex");
+}
+
+/// Elements that never hold article text.
+const SKIPPED: &[&str] = &[
+    "script", "style", "noscript", "nav", "aside", "footer", "header", "form", "button", "iframe", "svg", "canvas",
+    "template", "select", "input", "dialog",
+];
+
+const INLINE: &[&str] = &[
+    "a", "abbr", "b", "cite", "code", "del", "em", "i", "img", "kbd", "mark", "q", "s", "small", "span", "strong",
+    "sub", "sup", "time", "u", "var",
+];
+
+/// Tried in order before falling back to paragraph-density scoring.
+const CONTENT_SELECTORS: &[&str] = &[
+    "[itemprop='articleBody']",
+    "article",
+    "main",
+    "[role='main']",
+    ".post-content",
+    ".entry-content",
+    ".article-body",
+    ".article-content",
+    "#content",
+];
+
+const MIN_CONTENT_LENGTH: usize = 200;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentMode {
+    #[default]
+    Section,
+    Note,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ContentOptions {
+    #[serde(default)]
+    pub mode: ContentMode,
+    #[serde(default = "default_max_length")]
+    pub max_length: usize,
+}
+
+pub const DEFAULT_MAX_LENGTH: usize = 20000;
+
+fn default_max_length() -> usize {
+    DEFAULT_MAX_LENGTH
+}
+
+fn paragraph_length(element: ElementRef) -> usize {
+    let selector = Selector::parse("p").expect("Invalid paragraph selector");
+    element
+        .select(&selector)
+        .map(|p| p.text().map(str::trim).map(str::len).sum::<usize>())
+        .sum()
+}
+
+/// A small readability pass: prefer semantic containers, otherwise pick the
+/// element whose direct `<p>` children hold the most text.
+fn main_content(document: &Html) -> Option<ElementRef<'_>> {
+    for selector in CONTENT_SELECTORS {
+        let selector = Selector::parse(selector).expect("Invalid content selector");
+        if let Some(element) = document
+            .select(&selector)
+            .find(|element| paragraph_length(*element) >= MIN_CONTENT_LENGTH)
+        {
+            debug!("main_content: matched {}", element.value().name());
+            return Some(element);
+        }
+    }
+
+    let p_selector = Selector::parse("p").expect("Invalid paragraph selector");
+    let mut scores: HashMap<ego_tree::NodeId, usize> = HashMap::new();
+    for p in document.select(&p_selector) {
+        if let Some(parent) = p.parent() {
+            *scores.entry(parent.id()).or_default() += p.text().map(str::trim).map(str::len).sum::<usize>();
+        }
+    }
+    scores
+        .into_iter()
+        .max_by_key(|(_, score)| *score)
+        .and_then(|(id, _)| document.tree.get(id))
+        .and_then(ElementRef::wrap)
+        .or_else(|| {
+            let body = Selector::parse("body").expect("Invalid body selector");
+            document.select(&body).next()
+        })
+}
+
+struct Renderer<'a> {
+    base: Option<&'a Url>,
+}
+
+impl Renderer<'_> {
+    fn absolute(&self, link: &str) -> String {
+        match self.base {
+            Some(base) => base.join(link).map(|u| u.to_string()).unwrap_or_else(|_| link.to_string()),
+            None => link.to_string(),
+        }
+    }
+
+    fn image(&self, element: ElementRef) -> String {
+        let src = element
+            .value()
+            .attr("src")
+            .or_else(|| element.value().attr("data-src"))
+            .unwrap_or_default();
+        if src.is_empty() || src.starts_with("data:") {
+            return String::new();
+        }
+        let alt = element.value().attr("alt").unwrap_or_default().trim();
+        format!("![{}]({})", alt, self.absolute(src))
+    }
+
+    fn inline(&self, node: NodeRef<Node>) -> String {
+        match node.value() {
+            Node::Text(text) => WHITESPACE_RE.replace_all(text, " ").to_string(),
+            Node::Element(element) => {
+                let name = element.name();
+                if SKIPPED.contains(&name) {
+                    return String::new();
+                }
+                let Some(element_ref) = ElementRef::wrap(node) else {
+                    return String::new();
+                };
+                let inner = || node.children().map(|child| self.inline(child)).collect::<String>();
+                match name {
+                    "br" => "\n".to_string(),
+                    "img" => self.image(element_ref),
+                    "code" | "kbd" => {
+                        let code = element_ref.text().collect::<String>();
+                        if code.trim().is_empty() {
+                            String::new()
+                        } else {
+                            format!("`{}`", code.trim())
+                        }
+                    }
+                    "strong" | "b" => wrap(&inner(), "**"),
+                    "em" | "i" => wrap(&inner(), "*"),
+                    "del" | "s" => wrap(&inner(), "~~"),
+                    "a" => {
+                        let text = inner();
+                        match element.attr("href") {
+                            Some(href) if !href.starts_with('#') && !href.starts_with("javascript:") => {
+                                if text.trim().is_empty() {
+                                    String::new()
+                                } else {
+                                    format!("[{}]({})", text.trim(), self.absolute(href))
+                                }
+                            }
+                            _ => text,
+                        }
+                    }
+                    _ => inner(),
+                }
+            }
+            _ => String::new(),
+        }
+    }
+
+    fn flush(paragraph: &mut String, out: &mut String) {
+        let text = paragraph.trim();
+        if !text.is_empty() {
+            out.push_str("\n\n");
+            out.push_str(text);
+            out.push_str("\n\n");
+        }
+        paragraph.clear();
+    }
+
+    fn container(&self, node: NodeRef<Node>, out: &mut String) {
+        let mut paragraph = String::new();
+        for child in node.children() {
+            let is_inline = match child.value() {
+                Node::Text(_) => true,
+                Node::Element(element) => INLINE.contains(&element.name()),
+                _ => continue,
+            };
+            if is_inline {
+                paragraph.push_str(&self.inline(child));
+            } else {
+                Self::flush(&mut paragraph, out);
+                self.block(child, out);
+            }
+        }
+        Self::flush(&mut paragraph, out);
+    }
+
+    fn list(&self, node: NodeRef<Node>, ordered: bool, depth: usize, out: &mut String) {
+        let indent = "  ".repeat(depth);
+        let items = node
+            .children()
+            .filter(|child| matches!(child.value(), Node::Element(e) if e.name() == "li"));
+        for (index, item) in items.enumerate() {
+            let marker = if ordered { format!("{}.", index + 1) } else { "-".to_string() };
+            let mut text = String::new();
+            let mut nested = Vec::new();
+            for child in item.children() {
+                match child.value() {
+                    Node::Element(e) if e.name() == "ul" || e.name() == "ol" => nested.push((child, e.name() == "ol")),
+                    _ => text.push_str(&self.inline(child)),
+                }
+            }
+            out.push_str(&format!("{}{} {}\n", indent, marker, WHITESPACE_RE.replace_all(text.trim(), " ")));
+            for (list, ordered) in nested {
+                self.list(list, ordered, depth + 1, out);
+            }
+        }
+    }
+
+    fn block(&self, node: NodeRef<Node>, out: &mut String) {
+        let Node::Element(element) = node.value() else {
+            return;
+        };
+        let name = element.name();
+        if SKIPPED.contains(&name) {
+            return;
+        }
+        let Some(element_ref) = ElementRef::wrap(node) else {
+            return;
+        };
+
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name[1..].parse::<usize>().unwrap_or(2);
+                let text = self.inline(node);
+                let text = WHITESPACE_RE.replace_all(text.trim(), " ");
+                if !text.is_empty() {
+                    out.push_str(&format!("\n\n{} {}\n\n", "#".repeat(level), text));
+                }
+            }
+            "pre" => {
+                let language = std::iter::once(element_ref)
+                    .chain(element_ref.children().filter_map(ElementRef::wrap))
+                    .flat_map(|e| e.value().classes().map(str::to_string).collect::<Vec<_>>())
+                    .find_map(|class| {
+                        class
+                            .strip_prefix("language-")
+                            .or_else(|| class.strip_prefix("lang-"))
+                            .map(str::to_string)
+                    })
+                    .unwrap_or_default();
+                let code = element_ref.text().collect::<String>();
+                out.push_str(&format!("\n\n{}\n\n", code_block(&language, code.trim_end_matches('\n'))));
+            }
+            "ul" | "ol" => {
+                out.push_str("\n\n");
+                self.list(node, name == "ol", 0, out);
+                out.push('\n');
+            }
+            "blockquote" => {
+                let mut inner = String::new();
+                self.container(node, &mut inner);
+                let quoted = BLANK_LINES_RE
+                    .replace_all(inner.trim(), "\n\n")
+                    .lines()
+                    .map(|line| if line.is_empty() { ">".to_string() } else { format!("> {line}") })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                out.push_str(&format!("\n\n{quoted}\n\n"));
+            }
+            "hr" => out.push_str("\n\n---\n\n"),
+            "table" => self.table(element_ref, out),
+            _ => self.container(node, out),
+        }
+    }
+
+    fn table(&self, table: ElementRef, out: &mut String) {
+        let row_selector = Selector::parse("tr").expect("Invalid row selector");
+        let cell_selector = Selector::parse("th, td").expect("Invalid cell selector");
+        let rows: Vec<Vec<String>> = table
+            .select(&row_selector)
+            .map(|row| {
+                row.select(&cell_selector)
+                    .map(|cell| {
+                        let text = self.inline(*cell);
+                        WHITESPACE_RE.replace_all(text.trim(), " ").replace('|', "\\|")
+                    })
+                    .collect()
+            })
+            .filter(|cells: &Vec<String>| !cells.is_empty())
+            .collect();
+        let Some(columns) = rows.iter().map(Vec::len).max() else {
+            return;
+        };
+
+        out.push_str("\n\n");
+        for (index, row) in rows.iter().enumerate() {
+            let mut cells = row.clone();
+            cells.resize(columns, String::new());
+            out.push_str(&format!("| {} |\n", cells.join(" | ")));
+            if index == 0 {
+                out.push_str(&format!("|{}\n", " --- |".repeat(columns)));
+            }
+        }
+        out.push('\n');
+    }
+}
+
+fn wrap(text: &str, marker: &str) -> String {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        String::new()
+    } else {
+        format!("{marker}{trimmed}{marker}")
+    }
+}
+
+/// Fences `code` with one more backtick than the longest run inside it, so embedded fences can't close the block.
+pub fn code_block(language: &str, code: &str) -> String {
+    let longest_run = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    format!("{fence}{language}\n{code}\n{fence}")
+}
+
+/// Converts the page's main content to Markdown, resolving relative links
+/// and images against `url`.
+pub fn extract_markdown(content: &str, url: &str) -> String {
+    let document = Html::parse_document(content);
+    let Some(main) = main_content(&document) else {
+        return String::new();
+    };
+    let base = Url::parse(url).ok();
+    let renderer = Renderer { base: base.as_ref() };
+    let mut out = String::new();
+    renderer.container(*main, &mut out);
+    BLANK_LINES_RE.replace_all(out.trim(), "\n\n").to_string()
+}
+
+/// Converts an HTML snippet (a post body, a feed description) to Markdown.
+pub fn fragment_to_markdown(html: &str) -> String {
+    let fragment = Html::parse_fragment(html);
+    let renderer = Renderer { base: None };
+    let mut out = String::new();
+    renderer.container(*fragment.root_element(), &mut out);
+    BLANK_LINES_RE.replace_all(out.trim(), "\n\n").to_string()
+}
+
+/// Cuts Markdown to at most `max_length` bytes, preferring a paragraph boundary.
+pub fn truncate(markdown: &str, max_length: usize) -> String {
+    if markdown.len() <= max_length {
+        return markdown.to_string();
+    }
+    let mut end = max_length;
+    while !markdown.is_char_boundary(end) {
+        end -= 1;
+    }
+    let cut = &markdown[..end];
+    let cut = match cut.rfind("\n\n") {
+        Some(pos) if pos > max_length / 2 => &cut[..pos],
+        _ => cut,
+    };
+    format!("{}\n\n*(truncated)*", cut.trim_end())
+}
+
+/// Writes the content to a sibling note next to the bookmark and returns a link to it.
+pub fn write_note(title: &str, url: &str, markdown: &str, vault_path: &Path, folder: Option<String>) -> Result<String> {
+    let folder_path = vault_folder(vault_path, folder)?;
+    let file_path = new_note_path(&folder_path, &format!("{title} - Content"))?;
+    let note_name = file_path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    debug!("write_note: file_path={:?}", file_path);
+
+    let note = format!(
+        "---\nsource: {}\nurl: {}\ntype: content\n---\n\n{}\n",
+        quote_yaml(&format!("[[{}]]", sanitize_filename(title)?)),
+        url,
+        markdown
+    );
+    std::fs::write(&file_path, note)
+        .map_err(|e| eyre!("Failed to write content note: {:?} with error {}", file_path, e))?;
+    Ok(format!("[[{note_name}]]"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_markdown() {
+        let html = r#"<html><body>
+            <nav><a href="/">Home</a></nav>
+            <article>
+              <h1>The Title</h1>
+              <p>Intro with a <a href="/docs/page">relative link</a> and <strong>bold</strong> text that goes on for a while so the article counts as content.</p>
+              <h2>Steps</h2>
+              <ol><li>First <em>step</em></li><li>Second<ul><li>Nested</li></ul></li></ol>
+              <pre><code class="language-rust">fn main() {
+    println!("hi");
+}</code></pre>
+              <img src="img/diagram.png" alt="Diagram">
+              <blockquote><p>Quoted words</p></blockquote>
+              <p>More paragraph text padding out the article body so it passes the length threshold easily.</p>
+              <script>var tracking = true;</script>
+            </article>
+            <footer>Copyright</footer>
+            </body></html>"#;
+        let markdown = extract_markdown(html, "https://example.com/blog/post");
+        let expected = r#"# The Title
+
+Intro with a [relative link](https://example.com/docs/page) and **bold** text that goes on for a while so the article counts as content.
+
+## Steps
+
+1. First *step*
+2. Second
+  - Nested
+
+```rust
+fn main() {
+    println!("hi");
+}
+```
+
+![Diagram](https://example.com/blog/img/diagram.png)
+
+> Quoted words
+
+More paragraph text padding out the article body so it passes the length threshold easily."#;
+        assert_eq!(markdown, expected);
+    }
+
+    #[test]
+    fn test_truncate() {
+        let markdown = "First paragraph.\n\nSecond paragraph is longer.";
+        assert_eq!(truncate(markdown, 100), markdown);
+        assert_eq!(truncate(markdown, 30), "First paragraph.\n\n*(truncated)*");
+        assert_eq!(truncate("héllo wörld", 2), "h\n\n*(truncated)*");
+    }
+
+    #[test]
+    fn test_code_block_outlasts_inner_fences() {
+        assert_eq!(code_block("rust", "fn main() {}"), "```rust\nfn main() {}\n```");
+        assert_eq!(code_block("markdown", "````\nx\n````"), "`````markdown\n````\nx\n````\n`````");
+        assert_eq!(fragment_to_markdown("<pre>```\nx\n```</pre>"), "````\n```\nx\n```\n````");
+    }
+}
@@ -0,0 +1,169 @@
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, NaiveDateTime, SecondsFormat, TimeZone};
+use chrono_tz::Tz;
+use lazy_static::lazy_static;
+use log::debug;
+use regex::Regex;
+
+lazy_static! {
+    static ref ORDINAL_RE: Regex = Regex::new(r"(?i)\b(\d{1,2})(?:st|nd|rd|th)\b").expect("Invalid ordinal regex");
+    static ref WEEKDAY_RE: Regex =
+        Regex::new(r"(?i)^(?:mon|tue|wed|thu|fri|sat|sun)[a-z]*\.?,?\s+").expect("Invalid weekday regex");
+    static ref RELATIVE_RE: Regex = Regex::new(
+        r"(?i)^(?:about\s+)?(\d+|an?|one)\s+(second|sec|minute|min|hour|hr|day|week|month|year)s?\s+ago$"
+    )
+    .expect("Invalid relative date regex");
+}
+
+const DATE_TIME_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%B %d, %Y %I:%M %p",
+    "%B %d, %Y %H:%M",
+];
+
+const DATE_TIME_OFFSET_FORMATS: &[&str] = &["%Y-%m-%dT%H:%M:%S%.f%z", "%Y-%m-%d %H:%M:%S%.f %z"];
+
+const DATE_FORMATS: &[&str] = &[
+    "%Y-%m-%d",
+    "%Y/%m/%d",
+    "%Y.%m.%d",
+    "%B %d, %Y",
+    "%B %d %Y",
+    "%d %B %Y",
+    "%d %B, %Y",
+    "%m/%d/%Y",
+    "%Y%m%d",
+];
+
+fn format_date_time(value: DateTime<Tz>) -> String {
+    value.to_rfc3339_opts(SecondsFormat::Secs, false)
+}
+
+fn format_date(value: NaiveDate) -> String {
+    value.format("%Y-%m-%d").to_string()
+}
+
+fn relative(text: &str, now: DateTime<Tz>) -> Option<String> {
+    match text.to_lowercase().as_str() {
+        "now" | "just now" => return Some(format_date_time(now)),
+        "today" => return Some(format_date(now.date_naive())),
+        "yesterday" => return Some(format_date(now.date_naive() - Duration::days(1))),
+        _ => {}
+    }
+
+    let caps = RELATIVE_RE.captures(text)?;
+    let amount = match caps[1].to_lowercase().as_str() {
+        "a" | "an" | "one" => 1,
+        number => number.parse::<i64>().ok()?,
+    };
+    match caps[2].to_lowercase().as_str() {
+        "second" | "sec" => Some(format_date_time(now - Duration::seconds(amount))),
+        "minute" | "min" => Some(format_date_time(now - Duration::minutes(amount))),
+        "hour" | "hr" => Some(format_date_time(now - Duration::hours(amount))),
+        // Coarser units only identify a day, so don't invent a time of day.
+        "day" => Some(format_date(now.date_naive() - Duration::days(amount))),
+        "week" => Some(format_date(now.date_naive() - Duration::weeks(amount))),
+        "month" => now
+            .date_naive()
+            .checked_sub_months(Months::new(u32::try_from(amount).ok()?))
+            .map(format_date),
+        "year" => now
+            .date_naive()
+            .with_year(now.year() - i32::try_from(amount).ok()?)
+            .map(format_date),
+        _ => None,
+    }
+}
+
+/// Normalizes a published date to ISO 8601 in `now`'s timezone: a full timestamp
+/// when the source has a time, a bare date otherwise. Relative expressions
+/// ("3 days ago", "yesterday") are anchored to `now`. Unparseable values are
+/// returned unchanged so nothing the page said is lost.
+pub fn normalize(raw: &str, now: DateTime<Tz>) -> String {
+    let text = raw.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        return String::new();
+    }
+    let timezone = now.timezone();
+
+    if let Some(normalized) = relative(&text, now) {
+        return normalized;
+    }
+    if let Ok(value) = DateTime::parse_from_rfc3339(&text).or_else(|_| DateTime::parse_from_rfc2822(&text)) {
+        return format_date_time(value.with_timezone(&timezone));
+    }
+    for format in DATE_TIME_OFFSET_FORMATS {
+        if let Ok(value) = DateTime::parse_from_str(&text, format) {
+            return format_date_time(value.with_timezone(&timezone));
+        }
+    }
+
+    let cleaned = WEEKDAY_RE.replace(&text, "");
+    let cleaned = ORDINAL_RE.replace_all(&cleaned, "$1");
+    for format in DATE_TIME_FORMATS {
+        if let Ok(value) = NaiveDateTime::parse_from_str(&cleaned, format) {
+            if let Some(value) = timezone.from_local_datetime(&value).earliest() {
+                return format_date_time(value);
+            }
+        }
+    }
+    for format in DATE_FORMATS {
+        if let Ok(value) = NaiveDate::parse_from_str(&cleaned, format) {
+            return format_date(value);
+        }
+    }
+
+    debug!("normalize: unparseable date={:?}", raw);
+    raw.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Tz> {
+        chrono_tz::America::Los_Angeles
+            .with_ymd_and_hms(2024, 3, 15, 10, 30, 0)
+            .single()
+            .expect("valid date")
+    }
+
+    #[test]
+    fn test_normalize_absolute() {
+        let cases = [
+            ("2023-06-05T21:30:00Z", "2023-06-05T14:30:00-07:00"),
+            ("2023-06-05T14:30:00.000+0000", "2023-06-05T07:30:00-07:00"),
+            ("Mon, 05 Jun 2023 21:30:00 GMT", "2023-06-05T14:30:00-07:00"),
+            ("2023-06-05 14:30", "2023-06-05T14:30:00-07:00"),
+            ("2023-06-05", "2023-06-05"),
+            ("June 5th, 2023", "2023-06-05"),
+            ("Monday, Jun 5, 2023", "2023-06-05"),
+            ("5 June 2023", "2023-06-05"),
+            ("06/05/2023", "2023-06-05"),
+            ("  2023/06/05 ", "2023-06-05"),
+            ("", ""),
+            ("Spring 2023", "Spring 2023"),
+        ];
+        for (raw, expected) in cases {
+            assert_eq!(normalize(raw, now()), expected, "raw={raw:?}");
+        }
+    }
+
+    #[test]
+    fn test_normalize_relative() {
+        let cases = [
+            ("3 days ago", "2024-03-12"),
+            ("an hour ago", "2024-03-15T09:30:00-07:00"),
+            ("45 minutes ago", "2024-03-15T09:45:00-07:00"),
+            ("yesterday", "2024-03-14"),
+            ("2 weeks ago", "2024-03-01"),
+            ("1 month ago", "2024-02-15"),
+            ("about 2 years ago", "2022-03-15"),
+        ];
+        for (raw, expected) in cases {
+            assert_eq!(normalize(raw, now()), expected, "raw={raw:?}");
+        }
+    }
+}
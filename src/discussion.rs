@@ -0,0 +1,291 @@
+use chrono::DateTime;
+use eyre::{eyre, Result};
+use lazy_static::lazy_static;
+use log::{debug, error};
+use regex::Regex;
+use serde::Deserialize;
+use url::Url;
+
+use crate::{expanduser, frontmatter_value, http, notes, xml, Capture, Config};
+
+lazy_static! {
+    static ref PARAGRAPH_RE: Regex = Regex::new(r"(?i)<p>").expect("Invalid paragraph regex");
+    static ref TAG_RE: Regex = Regex::new(r"<[^>]+>").expect("Invalid tag regex");
+}
+
+/// Rule names whose folders hold discussion notes.
+pub const RULE_NAMES: &[&str] = &["hackernews", "reddit"];
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DiscussionOptions {
+    #[serde(default = "default_top_comments")]
+    pub top_comments: usize,
+}
+
+fn default_top_comments() -> usize {
+    5
+}
+
+impl Default for DiscussionOptions {
+    fn default() -> Self {
+        DiscussionOptions {
+            top_comments: default_top_comments(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Thread {
+    HackerNews(u64),
+    Reddit(String),
+}
+
+#[derive(Debug, Default)]
+struct Comment {
+    author: String,
+    text: String,
+    points: Option<i64>,
+}
+
+#[derive(Debug, Default)]
+struct Discussion {
+    title: String,
+    article_url: String,
+    text: String,
+    points: i64,
+    comment_count: u64,
+    submitter: String,
+    published: String,
+    community: String,
+    comments: Vec<Comment>,
+}
+
+pub fn parse_url(url: &str) -> Option<Thread> {
+    let parsed = Url::parse(url).ok()?;
+    let host = parsed.host_str()?;
+    let segments: Vec<&str> = parsed.path_segments()?.filter(|s| !s.is_empty()).collect();
+
+    match host {
+        "news.ycombinator.com" if segments == ["item"] => parsed
+            .query_pairs()
+            .find(|(key, _)| key == "id")
+            .and_then(|(_, id)| id.parse().ok())
+            .map(Thread::HackerNews),
+        "redd.it" => segments.first().map(|id| Thread::Reddit(id.to_string())),
+        host if host == "reddit.com" || host.ends_with(".reddit.com") => match segments.as_slice() {
+            ["r", _, "comments", id, ..] | ["comments", id, ..] => Some(Thread::Reddit(id.to_string())),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// HN comments are HTML fragments; notes want plain paragraphs.
+fn html_to_text(html: &str) -> String {
+    let text = PARAGRAPH_RE.replace_all(html, "\n\n");
+    xml::unescape(&TAG_RE.replace_all(&text, "")).trim().to_string()
+}
+
+fn timestamp(seconds: f64) -> String {
+    DateTime::from_timestamp(seconds as i64, 0)
+        .map(|date| date.to_rfc3339())
+        .unwrap_or_default()
+}
+
+async fn fetch_hacker_news(id: u64, top_comments: usize) -> Result<Discussion> {
+    let item_url = |id: &serde_json::Value| format!("https://hacker-news.firebaseio.com/v0/item/{id}.json");
+    let item = http::get_json(&item_url(&id.into())).await?;
+    if item.is_null() {
+        return Err(eyre!("Hacker News item {} not found", id));
+    }
+
+    // `kids` is already in ranked order.
+    let mut comments = Vec::new();
+    for kid in item["kids"].as_array().into_iter().flatten() {
+        if comments.len() >= top_comments {
+            break;
+        }
+        match http::get_json(&item_url(kid)).await {
+            Ok(comment) if comment["deleted"].as_bool() != Some(true) && comment["dead"].as_bool() != Some(true) => {
+                comments.push(Comment {
+                    author: comment["by"].as_str().unwrap_or_default().to_string(),
+                    text: html_to_text(comment["text"].as_str().unwrap_or_default()),
+                    points: None,
+                });
+            }
+            Ok(_) => {}
+            Err(e) => error!("Failed to fetch Hacker News comment {}: {:?}", kid, e),
+        }
+    }
+
+    Ok(Discussion {
+        title: item["title"].as_str().unwrap_or_default().to_string(),
+        article_url: item["url"].as_str().unwrap_or_default().to_string(),
+        text: html_to_text(item["text"].as_str().unwrap_or_default()),
+        points: item["score"].as_i64().unwrap_or_default(),
+        comment_count: item["descendants"].as_u64().unwrap_or_default(),
+        submitter: item["by"].as_str().unwrap_or_default().to_string(),
+        published: item["time"].as_f64().map(timestamp).unwrap_or_default(),
+        community: "Hacker News".to_string(),
+        comments,
+    })
+}
+
+fn parse_reddit(listings: &serde_json::Value, top_comments: usize) -> Result<Discussion> {
+    let post = &listings[0]["data"]["children"][0]["data"];
+    if post.is_null() {
+        return Err(eyre!("Reddit thread has no post"));
+    }
+    let str_field = |value: &serde_json::Value| value.as_str().unwrap_or_default().to_string();
+
+    let comments = listings[1]["data"]["children"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|child| child["kind"] == "t1" && child["data"]["stickied"].as_bool() != Some(true))
+        .map(|child| &child["data"])
+        .filter(|comment| !matches!(comment["author"].as_str(), Some("[deleted]") | Some("AutoModerator")))
+        .take(top_comments)
+        .map(|comment| Comment {
+            author: str_field(&comment["author"]),
+            text: str_field(&comment["body"]).trim().to_string(),
+            points: comment["score"].as_i64(),
+        })
+        .collect();
+
+    Ok(Discussion {
+        title: str_field(&post["title"]),
+        // Self posts link back to themselves.
+        article_url: if post["is_self"].as_bool() == Some(true) { String::new() } else { str_field(&post["url"]) },
+        text: str_field(&post["selftext"]).trim().to_string(),
+        points: post["score"].as_i64().unwrap_or_default(),
+        comment_count: post["num_comments"].as_u64().unwrap_or_default(),
+        submitter: str_field(&post["author"]),
+        published: post["created_utc"].as_f64().map(timestamp).unwrap_or_default(),
+        community: format!("r/{}", str_field(&post["subreddit"])),
+        comments,
+    })
+}
+
+fn render_comments(comments: &[Comment]) -> String {
+    comments
+        .iter()
+        .map(|comment| {
+            let quoted = comment
+                .text
+                .lines()
+                .map(|line| if line.is_empty() { ">".to_string() } else { format!("> {line}") })
+                .collect::<Vec<_>>()
+                .join("\n");
+            let points = comment.points.map(|points| format!(" ({points} points)")).unwrap_or_default();
+            format!("{quoted}\n>\n> — **{}**{}", comment.author, points)
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+pub async fn fetch(thread: &Thread, options: &DiscussionOptions) -> Result<Capture> {
+    debug!("fetch: thread={:?}", thread);
+    let discussion = match thread {
+        Thread::HackerNews(id) => fetch_hacker_news(*id, options.top_comments).await?,
+        Thread::Reddit(id) => {
+            let url = format!("https://www.reddit.com/comments/{id}.json?sort=top&limit={}", options.top_comments * 2);
+            parse_reddit(&http::get_json(&url).await?, options.top_comments)?
+        }
+    };
+    Ok(capture(discussion))
+}
+
+fn capture(discussion: Discussion) -> Capture {
+    let mut description = String::new();
+    if !discussion.article_url.is_empty() {
+        description.push_str(&format!("[Linked article]({})\n\n", discussion.article_url));
+    }
+    if !discussion.text.is_empty() {
+        description.push_str(&format!("{}\n\n", discussion.text));
+    }
+    if !discussion.comments.is_empty() {
+        description.push_str(&format!("## Top comments\n{}", render_comments(&discussion.comments)));
+    }
+
+    let mut extra = vec![
+        ("community".to_string(), discussion.community),
+        ("points".to_string(), discussion.points.to_string()),
+        ("comments".to_string(), discussion.comment_count.to_string()),
+    ];
+    if !discussion.article_url.is_empty() {
+        extra.push(("article_url".to_string(), discussion.article_url));
+    }
+
+    Capture {
+        title: discussion.title,
+        description: description.trim_end().to_string(),
+        authors: vec![discussion.submitter],
+        published: discussion.published,
+        extra,
+        ..Capture::default()
+    }
+}
+
+/// URLs of previously captured discussions whose `article_url` is one of `article_urls`.
+pub fn find_discussions(config: &Config, article_urls: &[&str]) -> Vec<String> {
+    let vault_path = expanduser(config.vault.to_string_lossy());
+    let mut discussions = Vec::new();
+    for link in config.links.iter().filter(|link| RULE_NAMES.contains(&link.name.as_str())) {
+        for path in notes(&vault_path.join(&link.folder)) {
+            let Ok(note) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            let matches = frontmatter_value(&note, "article_url").is_some_and(|url| article_urls.contains(&url.as_str()));
+            if let Some(url) = frontmatter_value(&note, "url").filter(|url| matches && !discussions.contains(url)) {
+                discussions.push(url);
+            }
+        }
+    }
+    discussions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_discussion_url() {
+        let cases = [
+            ("https://news.ycombinator.com/item?id=8863", Some(Thread::HackerNews(8863))),
+            ("https://www.reddit.com/r/rust/comments/abc123/some_title/", Some(Thread::Reddit("abc123".into()))),
+            ("https://old.reddit.com/r/rust/comments/abc123/", Some(Thread::Reddit("abc123".into()))),
+            ("https://redd.it/abc123", Some(Thread::Reddit("abc123".into()))),
+            ("https://news.ycombinator.com/news", None),
+            ("https://www.reddit.com/r/rust/", None),
+        ];
+        for (url, expected) in cases {
+            assert_eq!(parse_url(url), expected, "url={url}");
+        }
+    }
+
+    #[test]
+    fn test_reddit_capture() -> Result<()> {
+        let listings = json!([
+            {"data": {"children": [{"kind": "t3", "data": {"title": "Cool article", "url": "https://example.com/a",
+                "is_self": false, "score": 42, "num_comments": 7, "author": "poster", "subreddit": "rust",
+                "created_utc": 1700000000.0, "selftext": ""}}]}},
+            {"data": {"children": [
+                {"kind": "t1", "data": {"author": "AutoModerator", "body": "Rules", "score": 1}},
+                {"kind": "t1", "data": {"author": "alice", "body": "Great read.\n\nAgreed.", "score": 10}},
+                {"kind": "t1", "data": {"author": "bob", "body": "Meh", "score": 2}},
+                {"kind": "more", "data": {}}]}}
+        ]);
+        let capture = capture(parse_reddit(&listings, 1)?);
+        assert_eq!(capture.title, "Cool article");
+        assert_eq!(
+            capture.description,
+            "[Linked article](https://example.com/a)\n\n## Top comments\n> Great read.\n>\n> Agreed.\n>\n> — **alice** (10 points)"
+        );
+        assert!(capture.extra.contains(&("article_url".to_string(), "https://example.com/a".to_string())));
+        assert_eq!(capture.published, "2023-11-14T22:13:20+00:00");
+        assert_eq!(html_to_text("One<p>Two &#x27;quoted&#x27; <a href=\"x\">link</a>"), "One\n\nTwo 'quoted' link");
+        Ok(())
+    }
+}
@@ -0,0 +1,188 @@
+use encoding_rs::{Encoding, UTF_8};
+use eyre::Result;
+use lazy_static::lazy_static;
+use log::debug;
+use regex::bytes::Regex;
+
+use crate::http;
+
+lazy_static! {
+    static ref META_CHARSET_RE: Regex =
+        Regex::new(r#"(?i)<meta[^>]+charset\s*=\s*["']?\s*([A-Za-z0-9_:.-]+)"#).expect("Invalid meta charset regex");
+}
+
+/// How far into a document browsers look for a `<meta charset>` declaration.
+const META_SNIFF_LENGTH: usize = 1024;
+
+/// A fetched response, classified by what it actually contains.
+#[derive(Debug)]
+pub enum Payload {
+    Html(String),
+    Pdf(Vec<u8>),
+    Image { content_type: String, bytes: Vec<u8> },
+    Text { language: String, text: String },
+    /// Anything else (archives, audio, video, scripts); only worth a link.
+    File { content_type: String },
+}
+
+fn essence(content_type: &str) -> String {
+    content_type.split(';').next().unwrap_or_default().trim().to_lowercase()
+}
+
+fn charset(content_type: &str) -> Option<&'static Encoding> {
+    content_type
+        .split(';')
+        .skip(1)
+        .filter_map(|param| param.trim().split_once('='))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case("charset"))
+        .and_then(|(_, value)| Encoding::for_label(value.trim().trim_matches('"').as_bytes()))
+}
+
+/// The image type the leading bytes announce, whatever the server claimed.
+pub fn image_magic(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if bytes.starts_with(b"\xff\xd8\xff") {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if bytes.len() > 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+/// Decodes text using, in order of precedence, a byte order mark, the
+/// Content-Type charset, and (for HTML) an early `<meta charset>` declaration.
+pub fn decode(bytes: &[u8], content_type: &str, sniff_meta: bool) -> String {
+    let encoding = Encoding::for_bom(bytes)
+        .map(|(encoding, _)| encoding)
+        .or_else(|| charset(content_type))
+        .or_else(|| {
+            if !sniff_meta {
+                return None;
+            }
+            let head = &bytes[..bytes.len().min(META_SNIFF_LENGTH)];
+            META_CHARSET_RE
+                .captures(head)
+                .and_then(|caps| caps.get(1))
+                .and_then(|label| Encoding::for_label(label.as_bytes()))
+        })
+        .unwrap_or(UTF_8);
+    debug!("decode: content_type={} encoding={}", content_type, encoding.name());
+    let (text, _, _) = encoding.decode(bytes);
+    text.into_owned()
+}
+
+pub fn classify(content_type: &str, bytes: &[u8]) -> Payload {
+    let mime = essence(content_type);
+
+    // Magic bytes win because servers routinely label downloads application/octet-stream.
+    if bytes.starts_with(b"%PDF-") || mime == "application/pdf" {
+        return Payload::Pdf(bytes.to_vec());
+    }
+    if let Some(magic) = image_magic(bytes) {
+        return Payload::Image {
+            content_type: magic.to_string(),
+            bytes: bytes.to_vec(),
+        };
+    }
+    if mime.starts_with("image/") {
+        return Payload::Image {
+            content_type: mime,
+            bytes: bytes.to_vec(),
+        };
+    }
+    if mime == "application/json" || mime.ends_with("+json") {
+        let text = decode(bytes, content_type, false);
+        let text = serde_json::from_str::<serde_json::Value>(&text)
+            .and_then(|value| serde_json::to_string_pretty(&value))
+            .unwrap_or(text);
+        return Payload::Text {
+            language: "json".to_string(),
+            text,
+        };
+    }
+    let language = match mime.as_str() {
+        "text/plain" => Some(""),
+        "text/markdown" | "text/x-markdown" => Some("markdown"),
+        "text/csv" => Some("csv"),
+        "application/xml" | "text/xml" => Some("xml"),
+        _ => None,
+    };
+    if let Some(language) = language {
+        return Payload::Text {
+            language: language.to_string(),
+            text: decode(bytes, content_type, false),
+        };
+    }
+
+    // Servers that send no type at all are almost always serving a page.
+    if matches!(mime.as_str(), "text/html" | "application/xhtml+xml" | "") {
+        return Payload::Html(decode(bytes, content_type, true));
+    }
+    Payload::File { content_type: mime }
+}
+
+/// A response body together with the URL it was served from after redirects.
+#[derive(Debug)]
+pub struct Download {
+    pub final_url: String,
+    pub payload: Payload,
+}
+
+pub async fn download(url: &str) -> Result<Download> {
+    debug!("download: url={}", url);
+    let response = http::get(url).await?;
+    let final_url = response.url().to_string();
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    let bytes = http::bytes(response).await?;
+    debug!("download: url={} final_url={} content_type={}", url, final_url, content_type);
+    Ok(Download {
+        final_url,
+        payload: classify(&content_type, &bytes),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_charset_precedence() {
+        // "日本" in Shift_JIS
+        let shift_jis = b"<html><head><meta charset=\"Shift_JIS\"></head><body>\x93\xfa\x96\x7b</body></html>";
+        assert!(decode(shift_jis, "text/html", true).contains("日本"));
+        assert!(decode(b"caf\xe9", "text/html; charset=ISO-8859-1", true).contains("café"));
+        let meta_http_equiv = b"<meta http-equiv=\"Content-Type\" content=\"text/html; charset=windows-1252\">\x93q\x94";
+        assert!(decode(meta_http_equiv, "", true).ends_with("\u{201c}q\u{201d}"));
+        assert_eq!(decode("plain ✓".as_bytes(), "", true), "plain ✓");
+    }
+
+    #[test]
+    fn test_classify() {
+        assert!(matches!(classify("application/octet-stream", b"%PDF-1.7\n..."), Payload::Pdf(_)));
+        assert!(matches!(
+            classify("application/octet-stream", b"\x89PNG\r\n\x1a\n...."),
+            Payload::Image { ref content_type, .. } if content_type == "image/png"
+        ));
+        assert!(matches!(
+            classify("application/json; charset=utf-8", br#"{"a":1}"#),
+            Payload::Text { ref language, ref text } if language == "json" && text == "{\n  \"a\": 1\n}"
+        ));
+        assert!(matches!(classify("text/plain", b"hello"), Payload::Text { ref text, .. } if text == "hello"));
+        assert!(matches!(classify("text/html", b"<p>hi</p>"), Payload::Html(ref html) if html == "<p>hi</p>"));
+        assert!(matches!(classify("", b"<p>hi</p>"), Payload::Html(_)));
+        assert!(matches!(
+            classify("application/zip", b"PK\x03\x04"),
+            Payload::File { ref content_type } if content_type == "application/zip"
+        ));
+        assert!(matches!(classify("application/javascript", b"alert(1)"), Payload::File { .. }));
+    }
+}
@@ -0,0 +1,287 @@
+use chrono_tz::Tz;
+use eyre::{eyre, Result};
+use log::debug;
+use serde::Deserialize;
+use url::Url;
+
+use crate::{content, dates, http, Capture, GITHUB_TOKEN};
+
+/// How much of a README makes it into the note.
+const README_EXCERPT_LENGTH: usize = 3000;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GitHubOptions {
+    /// Points at a GitHub Enterprise instance, or a mock server in tests.
+    #[serde(default = "default_api_url")]
+    pub api_url: String,
+}
+
+fn default_api_url() -> String {
+    "https://api.github.com".to_string()
+}
+
+impl Default for GitHubOptions {
+    fn default() -> Self {
+        GitHubOptions {
+            api_url: default_api_url(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum GitHubUrl {
+    Repository { owner: String, repo: String },
+    Issue { owner: String, repo: String, number: u64 },
+    PullRequest { owner: String, repo: String, number: u64 },
+    Gist(String),
+}
+
+/// Top-level paths on github.com that aren't user or organisation names.
+const RESERVED_OWNERS: &[&str] = &[
+    "about", "apps", "collections", "enterprise", "explore", "features", "issues", "login", "marketplace",
+    "new", "notifications", "orgs", "organizations", "pricing", "pulls", "search", "settings", "sponsors",
+    "topics", "trending",
+];
+
+pub fn parse_url(url: &str) -> Option<GitHubUrl> {
+    let parsed = Url::parse(url).ok()?;
+    let host = parsed.host_str()?.trim_start_matches("www.");
+    let segments: Vec<&str> = parsed.path_segments()?.filter(|s| !s.is_empty()).collect();
+
+    if host == "gist.github.com" {
+        // Both gist.github.com/{user}/{id} and gist.github.com/{id} exist, and either may be
+        // followed by /raw/{sha}/{file} or /revisions. Usernames can be hex too, so a bare
+        // first segment only counts as an id at full gist-id length.
+        let is_hex = |segment: &str| !segment.is_empty() && segment.chars().all(|c| c.is_ascii_hexdigit());
+        let id = match segments.as_slice() {
+            [id, ..] if id.len() >= 20 && is_hex(id) => *id,
+            [_, id, ..] => id.trim_end_matches(".js").trim_end_matches(".git"),
+            _ => return None,
+        };
+        return is_hex(id).then(|| GitHubUrl::Gist(id.to_string()));
+    }
+    if host != "github.com" {
+        return None;
+    }
+
+    match segments.as_slice() {
+        [owner, ..] if RESERVED_OWNERS.contains(owner) => None,
+        [owner, repo, "issues", number, ..] => Some(GitHubUrl::Issue {
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            number: number.parse().ok()?,
+        }),
+        [owner, repo, "pull", number, ..] => Some(GitHubUrl::PullRequest {
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            number: number.parse().ok()?,
+        }),
+        [owner, repo, ..] => Some(GitHubUrl::Repository {
+            owner: owner.to_string(),
+            repo: repo.trim_end_matches(".git").to_string(),
+        }),
+        _ => None,
+    }
+}
+
+fn str_field(value: &serde_json::Value) -> String {
+    value.as_str().unwrap_or_default().to_string()
+}
+
+fn names(values: &serde_json::Value, key: &str) -> Vec<String> {
+    values
+        .as_array()
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|value| value.as_str().or_else(|| value[key].as_str()))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+async fn get(options: &GitHubOptions, path: &str, accept: &str) -> Result<reqwest::Response> {
+    let url = format!("{}{}", options.api_url.trim_end_matches('/'), path);
+    debug!("get: url={}", url);
+    let mut request = http::client()
+        .get(&url)
+        .header("Accept", accept)
+        .header("X-GitHub-Api-Version", "2022-11-28");
+    if let Some(token) = GITHUB_TOKEN.as_deref() {
+        request = request.bearer_auth(token);
+    }
+    let response = http::send(request).await?;
+    if !response.status().is_success() {
+        return Err(eyre!("GitHub request to {} failed with status {}", url, response.status()));
+    }
+    Ok(response)
+}
+
+async fn get_json(options: &GitHubOptions, path: &str) -> Result<serde_json::Value> {
+    http::json(get(options, path, "application/vnd.github+json").await?).await
+}
+
+pub async fn fetch(options: &GitHubOptions, github_url: &GitHubUrl, timezone: Tz) -> Result<Capture> {
+    debug!("fetch: github_url={:?}", github_url);
+    match github_url {
+        GitHubUrl::Repository { owner, repo } => {
+            let repository = get_json(options, &format!("/repos/{owner}/{repo}")).await?;
+            // Plenty of repositories have no README; that shouldn't fail the capture.
+            let readme = match get(options, &format!("/repos/{owner}/{repo}/readme"), "application/vnd.github.raw").await {
+                Ok(response) => http::text(response).await.unwrap_or_default(),
+                Err(e) => {
+                    debug!("No README for {}/{}: {:?}", owner, repo, e);
+                    String::new()
+                }
+            };
+            Ok(repository_capture(&repository, &readme, timezone))
+        }
+        GitHubUrl::Issue { owner, repo, number } => {
+            let issue = get_json(options, &format!("/repos/{owner}/{repo}/issues/{number}")).await?;
+            Ok(issue_capture(repo, &issue, "Issue"))
+        }
+        GitHubUrl::PullRequest { owner, repo, number } => {
+            let pull = get_json(options, &format!("/repos/{owner}/{repo}/pulls/{number}")).await?;
+            Ok(issue_capture(repo, &pull, "PR"))
+        }
+        GitHubUrl::Gist(id) => Ok(gist_capture(&get_json(options, &format!("/gists/{id}")).await?)),
+    }
+}
+
+fn repository_capture(repository: &serde_json::Value, readme: &str, timezone: Tz) -> Capture {
+    let mut description = str_field(&repository["description"]);
+    let readme = content::truncate(readme.trim(), README_EXCERPT_LENGTH);
+    if !readme.is_empty() {
+        description = format!("{description}\n\n## README\n{readme}");
+    }
+
+    let mut extra = vec![];
+    for (key, value) in [
+        ("language", str_field(&repository["language"])),
+        ("stars", repository["stargazers_count"].as_u64().map(|stars| stars.to_string()).unwrap_or_default()),
+        ("license", str_field(&repository["license"]["spdx_id"])),
+        ("last_push", dates::normalize(&str_field(&repository["pushed_at"]), chrono::Utc::now().with_timezone(&timezone))),
+    ] {
+        if !value.is_empty() && value != "NOASSERTION" {
+            extra.push((key.to_string(), value));
+        }
+    }
+
+    Capture {
+        title: format!("{} - {}", str_field(&repository["owner"]["login"]), str_field(&repository["name"])),
+        description,
+        authors: vec![str_field(&repository["owner"]["login"])],
+        tags: names(&repository["topics"], "name"),
+        published: str_field(&repository["created_at"]),
+        extra,
+        ..Capture::default()
+    }
+}
+
+fn issue_capture(repo: &str, issue: &serde_json::Value, kind: &str) -> Capture {
+    let state = if issue["merged"].as_bool() == Some(true) {
+        "merged".to_string()
+    } else {
+        str_field(&issue["state"])
+    };
+    Capture {
+        title: format!("{} {} {} - {}", repo, kind, issue["number"], str_field(&issue["title"])),
+        description: str_field(&issue["body"]),
+        authors: vec![str_field(&issue["user"]["login"])],
+        tags: names(&issue["labels"], "name"),
+        published: str_field(&issue["created_at"]),
+        extra: vec![("state".to_string(), state)],
+        ..Capture::default()
+    }
+}
+
+fn gist_capture(gist: &serde_json::Value) -> Capture {
+    let mut files: Vec<(&String, &serde_json::Value)> = gist["files"]
+        .as_object()
+        .map(|files| files.iter().collect())
+        .unwrap_or_default();
+    files.sort_by_key(|(name, _)| name.as_str());
+
+    let mut description = str_field(&gist["description"]);
+    for (name, file) in &files {
+        let language = str_field(&file["language"]).to_lowercase();
+        description.push_str(&format!("\n\n### {}\n{}", name, content::code_block(&language, str_field(&file["content"]).trim_end())));
+    }
+
+    let title = match str_field(&gist["description"]) {
+        title if title.is_empty() => files.first().map(|(name, _)| name.to_string()).unwrap_or_default(),
+        title => title,
+    };
+
+    Capture {
+        title,
+        description: description.trim_start().to_string(),
+        authors: vec![str_field(&gist["owner"]["login"])],
+        published: str_field(&gist["created_at"]),
+        ..Capture::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_github_url() {
+        let cases = [
+            ("https://github.com/rust-lang/rust", Some(GitHubUrl::Repository { owner: "rust-lang".into(), repo: "rust".into() })),
+            ("https://github.com/rust-lang/rust.git", Some(GitHubUrl::Repository { owner: "rust-lang".into(), repo: "rust".into() })),
+            ("https://github.com/rust-lang/rust/tree/master/src", Some(GitHubUrl::Repository { owner: "rust-lang".into(), repo: "rust".into() })),
+            ("https://github.com/rust-lang/rust/issues/42", Some(GitHubUrl::Issue { owner: "rust-lang".into(), repo: "rust".into(), number: 42 })),
+            ("https://github.com/rust-lang/rust/pull/7#discussion", Some(GitHubUrl::PullRequest { owner: "rust-lang".into(), repo: "rust".into(), number: 7 })),
+            ("https://gist.github.com/someone/abc123", Some(GitHubUrl::Gist("abc123".into()))),
+            ("https://gist.github.com/someone/abc123/raw/def456/main.rs", Some(GitHubUrl::Gist("abc123".into()))),
+            ("https://gist.github.com/aa5a315d61ae9438b18d", Some(GitHubUrl::Gist("aa5a315d61ae9438b18d".into()))),
+            ("https://gist.github.com/someone", None),
+            ("https://github.com/topics/rust", None),
+            ("https://github.com/rust-lang", None),
+        ];
+        for (url, expected) in cases {
+            assert_eq!(parse_url(url), expected, "url={url}");
+        }
+    }
+
+    #[test]
+    fn test_captures() {
+        let pull = json!({"number": 7, "title": "Fix it", "state": "closed", "merged": true, "body": "Details",
+            "user": {"login": "octocat"}, "labels": [{"name": "bug"}], "created_at": "2024-01-02T03:04:05Z"});
+        let capture = issue_capture("rust", &pull, "PR");
+        assert_eq!(capture.title, "rust PR 7 - Fix it");
+        assert_eq!(capture.tags, vec!["bug".to_string()]);
+        assert_eq!(capture.extra, vec![("state".to_string(), "merged".to_string())]);
+
+        let gist = json!({"description": "", "owner": {"login": "octocat"}, "files": {
+            "b.py": {"language": "Python", "content": "print(1)\n"},
+            "c.md": {"language": "Markdown", "content": "```sh\nls\n```"},
+            "a.rs": {"language": "Rust", "content": "fn main() {}"}}});
+        let capture = gist_capture(&gist);
+        assert_eq!(capture.title, "a.rs");
+        assert_eq!(capture.description, "### a.rs\n```rust\nfn main() {}\n```\n\n### b.py\n```python\nprint(1)\n```\n\n### c.md\n````markdown\n```sh\nls\n```\n````");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_gist_from_api_url() -> Result<()> {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/gists/abc123")
+            .match_header("accept", "application/vnd.github+json")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"description": "Hello", "owner": {"login": "octocat"}, "files": {"hello.rs": {"language": "Rust", "content": "fn main() {}"}}}"#)
+            .create_async()
+            .await;
+        let options = GitHubOptions { api_url: server.url() };
+        let gist = parse_url("https://gist.github.com/octocat/abc123/raw/def456/hello.rs").expect("expected a gist");
+        let capture = fetch(&options, &gist, chrono_tz::UTC).await?;
+        mock.assert_async().await;
+        assert_eq!(capture.title, "Hello");
+        assert_eq!(capture.authors, vec!["octocat".to_string()]);
+        Ok(())
+    }
+}
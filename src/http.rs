@@ -0,0 +1,223 @@
+use eyre::{eyre, Result};
+use log::{debug, info, warn};
+use reqwest::header::{HeaderValue, RETRY_AFTER};
+use reqwest::{redirect, Client, IntoUrl, Proxy, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+static HTTP: OnceLock<Http> = OnceLock::new();
+
+/// Upper bound for a single backoff so a hostile `Retry-After` can't stall a request.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct HttpOptions {
+    #[serde(default = "default_user_agent")]
+    pub user_agent: String,
+    /// Seconds.
+    #[serde(default = "default_connect_timeout")]
+    pub connect_timeout: u64,
+    /// Seconds allowed between reads of the response body.
+    #[serde(default = "default_read_timeout")]
+    pub read_timeout: u64,
+    #[serde(default = "default_max_redirects")]
+    pub max_redirects: usize,
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// Bytes.
+    #[serde(default = "default_max_body_size")]
+    pub max_body_size: usize,
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+}
+
+fn default_user_agent() -> String {
+    format!(
+        "Mozilla/5.0 (compatible; obsidian-bookmark/{})",
+        env!("CARGO_PKG_VERSION")
+    )
+}
+
+fn default_connect_timeout() -> u64 {
+    10
+}
+
+fn default_read_timeout() -> u64 {
+    30
+}
+
+fn default_max_redirects() -> usize {
+    10
+}
+
+fn default_max_body_size() -> usize {
+    20 * 1024 * 1024
+}
+
+fn default_retries() -> u32 {
+    2
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        HttpOptions {
+            user_agent: default_user_agent(),
+            connect_timeout: default_connect_timeout(),
+            read_timeout: default_read_timeout(),
+            max_redirects: default_max_redirects(),
+            proxy: None,
+            max_body_size: default_max_body_size(),
+            retries: default_retries(),
+        }
+    }
+}
+
+struct Http {
+    client: Client,
+    max_body_size: usize,
+    retries: u32,
+}
+
+fn build(options: &HttpOptions) -> Result<Http> {
+    let mut builder = Client::builder()
+        .user_agent(&options.user_agent)
+        .connect_timeout(Duration::from_secs(options.connect_timeout))
+        .read_timeout(Duration::from_secs(options.read_timeout))
+        .redirect(redirect::Policy::limited(options.max_redirects));
+    if let Some(proxy) = options.proxy.as_deref().filter(|proxy| !proxy.is_empty()) {
+        builder = builder.proxy(Proxy::all(proxy).map_err(|e| eyre!("Invalid proxy {}: {}", proxy, e))?);
+    }
+    Ok(Http {
+        client: builder
+            .build()
+            .map_err(|e| eyre!("Failed to build HTTP client: {}", e))?,
+        max_body_size: options.max_body_size,
+        retries: options.retries,
+    })
+}
+
+/// Builds the shared client from config. Must run before the first request;
+/// otherwise the defaults are used.
+pub fn init(options: &HttpOptions) -> Result<()> {
+    info!(
+        "init: user_agent={} proxy={:?} retries={}",
+        options.user_agent, options.proxy, options.retries
+    );
+    HTTP.set(build(options)?)
+        .map_err(|_| eyre!("HTTP client already initialised"))
+}
+
+fn http() -> &'static Http {
+    HTTP.get_or_init(|| build(&HttpOptions::default()).expect("Failed to build default HTTP client"))
+}
+
+pub fn client() -> &'static Client {
+    &http().client
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Exponential backoff from 500ms, unless the server asked for a specific delay.
+fn retry_delay(attempt: u32, retry_after: Option<&HeaderValue>) -> Duration {
+    let requested = retry_after
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs);
+    requested
+        .unwrap_or_else(|| Duration::from_millis(500u64.saturating_mul(2u64.saturating_pow(attempt))))
+        .min(MAX_RETRY_DELAY)
+}
+
+/// Sends a request, retrying on connection errors, 429 and 5xx responses.
+pub async fn send(request: RequestBuilder) -> Result<Response> {
+    let retries = http().retries;
+    let mut attempt = 0;
+    loop {
+        let Some(this_try) = request.try_clone() else {
+            // Streaming bodies can't be replayed.
+            return Ok(request.send().await?);
+        };
+        let delay = match this_try.send().await {
+            Ok(response) if attempt < retries && is_retryable(response.status()) => {
+                warn!("send: url={} status={} attempt={}", response.url(), response.status(), attempt);
+                retry_delay(attempt, response.headers().get(RETRY_AFTER))
+            }
+            Err(e) if attempt < retries && (e.is_connect() || e.is_timeout()) => {
+                warn!("send: error={} attempt={}", e, attempt);
+                retry_delay(attempt, None)
+            }
+            result => return Ok(result?),
+        };
+        debug!("send: retrying in {:?}", delay);
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+pub async fn get<U: IntoUrl>(url: U) -> Result<Response> {
+    send(client().get(url)).await
+}
+
+/// Reads the body, refusing anything over the configured size.
+pub async fn bytes(mut response: Response) -> Result<Vec<u8>> {
+    let limit = http().max_body_size;
+    if response.content_length().is_some_and(|length| length as usize > limit) {
+        return Err(eyre!("Response from {} exceeds {} bytes", response.url(), limit));
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if body.len() + chunk.len() > limit {
+            return Err(eyre!("Response from {} exceeds {} bytes", response.url(), limit));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+pub async fn text(response: Response) -> Result<String> {
+    Ok(String::from_utf8_lossy(&bytes(response).await?).into_owned())
+}
+
+pub async fn json<T: DeserializeOwned>(response: Response) -> Result<T> {
+    Ok(serde_json::from_slice(&bytes(response).await?)?)
+}
+
+/// GETs `url` and parses the body as JSON, treating any non-success status as an error.
+pub async fn get_json(url: &str) -> Result<serde_json::Value> {
+    let response = get(url).await?;
+    if !response.status().is_success() {
+        return Err(eyre!("Request to {} failed with status {}", url, response.status()));
+    }
+    json(response).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(retry_delay(0, None), Duration::from_millis(500));
+        assert_eq!(retry_delay(2, None), Duration::from_secs(2));
+        assert_eq!(retry_delay(10, None), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(0, Some(&HeaderValue::from_static("3"))), Duration::from_secs(3));
+        assert_eq!(retry_delay(0, Some(&HeaderValue::from_static("3600"))), MAX_RETRY_DELAY);
+        assert!(is_retryable(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable(StatusCode::BAD_GATEWAY));
+        assert!(!is_retryable(StatusCode::NOT_FOUND));
+    }
+
+    #[test]
+    fn test_http_options_defaults() -> Result<()> {
+        let options: HttpOptions = serde_yaml::from_str("proxy: http://127.0.0.1:3128\nretries: 0")?;
+        assert_eq!(options.retries, 0);
+        assert_eq!(options.max_redirects, 10);
+        assert!(options.user_agent.starts_with("Mozilla/5.0"));
+        assert!(build(&options).is_ok());
+        Ok(())
+    }
+}
@@ -0,0 +1,224 @@
+use log::debug;
+use scraper::{Html, Selector};
+use serde_json::Value;
+
+/// schema.org types we know how to read, most specific first. When a page
+/// ships several entities the earliest type in this list wins.
+const PRIMARY_TYPES: &[&str] = &[
+    "NewsArticle",
+    "BlogPosting",
+    "TechArticle",
+    "ScholarlyArticle",
+    "Report",
+    "Article",
+    "VideoObject",
+    "Recipe",
+    "Product",
+    "Event",
+    "Book",
+    "Course",
+    "SoftwareApplication",
+    "PodcastEpisode",
+    "Review",
+    "HowTo",
+    "WebPage",
+];
+
+#[derive(Debug, Default, Clone)]
+pub struct JsonLd {
+    pub kind: String,
+    pub headline: String,
+    pub description: String,
+    pub authors: Vec<String>,
+    pub published: String,
+    pub image: String,
+    pub keywords: Vec<String>,
+    pub publisher: String,
+}
+
+fn types(entity: &Value) -> Vec<&str> {
+    match &entity["@type"] {
+        Value::String(kind) => vec![kind.as_str()],
+        Value::Array(kinds) => kinds.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn collect_entities(value: &Value, entities: &mut Vec<Value>) {
+    match value {
+        Value::Array(items) => items.iter().for_each(|item| collect_entities(item, entities)),
+        Value::Object(object) => {
+            if let Some(graph) = object.get("@graph") {
+                collect_entities(graph, entities);
+            }
+            if object.contains_key("@type") {
+                entities.push(value.clone());
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Returns every typed entity in the page's `application/ld+json` blocks,
+/// flattening top-level arrays and `@graph` containers.
+pub fn entities(document: &Html) -> Vec<Value> {
+    let selector = Selector::parse("script[type='application/ld+json']").expect("Invalid JSON-LD selector");
+    let mut entities = Vec::new();
+    for script in document.select(&selector) {
+        let text = script.text().collect::<String>();
+        match serde_json::from_str::<Value>(text.trim()) {
+            Ok(value) => collect_entities(&value, &mut entities),
+            Err(e) => debug!("Skipping malformed JSON-LD block: {}", e),
+        }
+    }
+    entities
+}
+
+pub fn find_entity(entities: &[Value], kind: &str) -> Option<Value> {
+    entities.iter().find(|entity| types(entity).contains(&kind)).cloned()
+}
+
+pub fn text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.trim().to_string(),
+        Value::Array(items) => items.first().map(text).unwrap_or_default(),
+        Value::Object(object) => object
+            .get("name")
+            .or_else(|| object.get("@value"))
+            .map(text)
+            .unwrap_or_default(),
+        Value::Number(n) => n.to_string(),
+        _ => String::new(),
+    }
+}
+
+pub fn names(value: &Value) -> Vec<String> {
+    match value {
+        Value::Array(items) => items.iter().flat_map(names).collect(),
+        Value::Null => Vec::new(),
+        _ => Some(text(value)).filter(|name| !name.is_empty()).into_iter().collect(),
+    }
+}
+
+pub fn image_url(value: &Value) -> String {
+    match value {
+        Value::String(url) => url.clone(),
+        Value::Array(items) => items.iter().map(image_url).find(|url| !url.is_empty()).unwrap_or_default(),
+        Value::Object(object) => object
+            .get("url")
+            .or_else(|| object.get("contentUrl"))
+            .map(image_url)
+            .unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+pub fn keywords(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(keywords) => keywords
+            .split(',')
+            .map(|keyword| keyword.trim().to_string())
+            .filter(|keyword| !keyword.is_empty())
+            .collect(),
+        Value::Array(items) => items.iter().flat_map(keywords).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn from_entity(entity: &Value) -> JsonLd {
+    let kind = types(entity)
+        .into_iter()
+        .find(|kind| PRIMARY_TYPES.contains(kind))
+        .unwrap_or_default()
+        .to_string();
+    let published = ["datePublished", "uploadDate", "startDate", "dateCreated"]
+        .iter()
+        .map(|key| text(&entity[key]))
+        .find(|date| !date.is_empty())
+        .unwrap_or_default();
+    // A bare `WebPage` name is usually the `<title>`, site name and all; the page's own title handling does better.
+    let headline = if kind == "WebPage" {
+        String::new()
+    } else {
+        ["headline", "name"]
+            .iter()
+            .map(|key| text(&entity[key]))
+            .find(|headline| !headline.is_empty())
+            .unwrap_or_default()
+    };
+    let image = ["image", "thumbnailUrl"]
+        .iter()
+        .map(|key| image_url(&entity[key]).trim().to_string())
+        .find(|image| !image.is_empty())
+        .unwrap_or_default();
+    let authors = ["author", "creator", "organizer", "brand"]
+        .iter()
+        .map(|key| names(&entity[key]))
+        .find(|authors| !authors.is_empty())
+        .unwrap_or_default();
+
+    JsonLd {
+        kind,
+        headline,
+        description: text(&entity["description"]),
+        authors,
+        published,
+        image,
+        keywords: keywords(&entity["keywords"]),
+        publisher: text(&entity["publisher"]),
+    }
+}
+
+/// Picks the page's primary schema.org entity among `entities` and flattens the fields we care about.
+pub fn extract(entities: &[Value]) -> Option<JsonLd> {
+    let primary = PRIMARY_TYPES
+        .iter()
+        .find_map(|kind| find_entity(entities, kind))?;
+    debug!("jsonld::extract: primary type={:?}", primary["@type"]);
+    Some(from_entity(&primary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_article_from_graph() {
+        let html = r#"<html><head>
+            <script type="application/ld+json">{"@context":"https://schema.org","@graph":[
+                {"@type":"WebPage","name":"Article | Site Name"},
+                {"@type":["NewsArticle"],"headline":"Real Headline",
+                 "author":[{"@type":"Person","name":"Jane Doe"},{"@type":"Person","name":"John Roe"}],
+                 "datePublished":"2023-06-05T10:00:00Z",
+                 "image":[{"@type":"ImageObject","url":"https://example.com/hero.jpg"}],
+                 "keywords":"rust, parsing",
+                 "publisher":{"@type":"Organization","name":"Example News"}}
+            ]}</script>
+            <script type="application/ld+json">{not json</script>
+            </head></html>"#;
+        let data = extract(&entities(&Html::parse_document(html))).expect("expected JSON-LD");
+        assert_eq!(data.kind, "NewsArticle");
+        assert_eq!(data.headline, "Real Headline");
+        assert_eq!(data.authors, vec!["Jane Doe".to_string(), "John Roe".to_string()]);
+        assert_eq!(data.published, "2023-06-05T10:00:00Z");
+        assert_eq!(data.image, "https://example.com/hero.jpg");
+        assert_eq!(data.keywords, vec!["rust".to_string(), "parsing".to_string()]);
+        assert_eq!(data.publisher, "Example News");
+
+        let web_page = r#"<script type="application/ld+json">{"@type":"WebPage","name":"Article | Site Name","datePublished":"2023-06-05"}</script>"#;
+        let data = extract(&entities(&Html::parse_document(web_page))).expect("expected JSON-LD");
+        assert_eq!(data.headline, "");
+        assert_eq!(data.published, "2023-06-05");
+    }
+
+    #[test]
+    fn test_extract_video_object_upload_date() {
+        let html = r#"<script type="application/ld+json">[{"@type":"VideoObject","name":"Clip","uploadDate":"2022-01-02","thumbnailUrl":"https://example.com/thumb.jpg","author":"Someone"}]</script>"#;
+        let data = extract(&entities(&Html::parse_document(html))).expect("expected JSON-LD");
+        assert_eq!(data.kind, "VideoObject");
+        assert_eq!(data.published, "2022-01-02");
+        assert_eq!(data.authors, vec!["Someone".to_string()]);
+        assert_eq!(data.image, "https://example.com/thumb.jpg");
+        assert!(extract(&entities(&Html::parse_document("<p>none</p>"))).is_none());
+    }
+}
@@ -14,14 +14,55 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::collections::{HashMap, HashSet};
 use std::env;
-use std::io::Write;
+use std::io::{Read, Write};
 use std::path::{Path,PathBuf};
 use url::Url;
 
+mod archive;
+mod attachments;
+mod authors;
+mod book;
+mod content;
+mod dates;
+mod discussion;
+mod download;
+mod github;
+mod http;
+mod jsonld;
+mod oembed;
+mod opengraph;
+mod package;
+mod paper;
+mod pdf;
+mod podcast;
+mod recipe;
+mod social;
+mod stackexchange;
+mod video;
+mod wikipedia;
+mod xml;
+mod youtube;
+
+use archive::ArchiveOptions;
+use attachments::Attachments;
+use book::{BookOptions, BookUrl};
+use content::{ContentMode, ContentOptions};
+use discussion::DiscussionOptions;
+use download::Payload;
+use github::GitHubOptions;
+use paper::PaperOptions;
+use http::HttpOptions;
+use oembed::OEmbedOptions;
+use video::VideoPlatform;
+use youtube::YouTubeKind;
+
 lazy_static! {
     static ref OBSIDIAN_BOOKMARK_PORT: String = env::var("OBSIDIAN_BOOKMARK_PORT").unwrap_or_else(|_| "65000".to_string());
-    static ref TIMEZONE: Tz = "America/Los_Angeles".parse().expect("Invalid timezone");
-    static ref YOUTUBE_API_KEY: String = env::var("YOUTUBE_API_KEY").expect("YOUTUBE_API_KEY not set in environment");
+    static ref YOUTUBE_API_KEY: Option<String> = env::var("YOUTUBE_API_KEY").ok();
+    static ref TWITCH_CLIENT_ID: Option<String> = env::var("TWITCH_CLIENT_ID").ok();
+    static ref TWITCH_ACCESS_TOKEN: Option<String> = env::var("TWITCH_ACCESS_TOKEN").ok();
+    static ref GITHUB_TOKEN: Option<String> = env::var("GITHUB_TOKEN").ok();
+    static ref STACKEXCHANGE_KEY: Option<String> = env::var("STACKEXCHANGE_KEY").ok();
     static ref CHATGPT_API_KEY: String = env::var("CHATGPT_API_KEY").expect("CHATGPT_API_KEY not set in environment");
     static ref RESOLUTIONS: HashMap<&'static str, (usize, usize)> = {
         let mut m = HashMap::new();
@@ -51,6 +92,9 @@ lazy_static! {
     };
 }
 
+/// How much of each note `find_note_by_url` reads looking for its `url:`; frontmatter comes first.
+const FRONTMATTER_SCAN_BYTES: u64 = 4096;
+
 #[derive(Parser, Debug)]
 #[command(name = "obsidian-bookmark", version = env!("GIT_DESCRIBE"), about = "Obsidian bookmark manager")]
 struct Cli {
@@ -84,8 +128,32 @@ struct Frontmatter {
     time: String,
     tags: Vec<String>,
     url: String,
-    author: String,
+    #[serde(default, deserialize_with = "one_or_many")]
+    author: Vec<String>,
     published: String,
+    /// Written as `type:`; site handlers override it for things that aren't plain links.
+    #[serde(default, rename = "type")]
+    note_type: String,
+    #[serde(skip)]
+    extra: Vec<(String, String)>,
+}
+
+/// Accepts either a single string or a list, so older configs with `author: Name` still load.
+fn one_or_many<'de, D>(deserializer: D) -> std::result::Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match Option::<OneOrMany>::deserialize(deserializer)? {
+        Some(OneOrMany::One(value)) if !value.is_empty() => vec![value],
+        Some(OneOrMany::Many(values)) => values,
+        _ => Vec::new(),
+    })
 }
 
 impl Frontmatter {
@@ -98,6 +166,8 @@ impl Frontmatter {
             url: if actual.url.is_empty() { self.url.clone() } else { actual.url.clone() },
             author: if actual.author.is_empty() { self.author.clone() } else { actual.author.clone() },
             published: if actual.published.is_empty() { self.published.clone() } else { actual.published.clone() },
+            note_type: if actual.note_type.is_empty() { self.note_type.clone() } else { actual.note_type.clone() },
+            extra: if actual.extra.is_empty() { self.extra.clone() } else { actual.extra.clone() },
         }
     }
 }
@@ -107,6 +177,35 @@ struct Config {
     vault: PathBuf,
     frontmatter: Frontmatter,
     links: Vec<Link>,
+    #[serde(default = "default_channel_folder")]
+    channel_folder: String,
+    #[serde(default)]
+    attachments: Attachments,
+    #[serde(default)]
+    http: HttpOptions,
+    /// IANA name, e.g. `Europe/Berlin`; capture dates and normalized published dates use it.
+    #[serde(default = "default_timezone")]
+    timezone: Tz,
+    #[serde(default)]
+    author_wikilinks: bool,
+    #[serde(default)]
+    github: GitHubOptions,
+    #[serde(default)]
+    papers: PaperOptions,
+    #[serde(default)]
+    discussions: DiscussionOptions,
+    #[serde(default)]
+    books: BookOptions,
+    #[serde(default)]
+    oembed: OEmbedOptions,
+}
+
+fn default_channel_folder() -> String {
+    "youtube/channels".to_string()
+}
+
+fn default_timezone() -> Tz {
+    chrono_tz::America::Los_Angeles
 }
 
 impl Config {
@@ -119,6 +218,8 @@ impl Config {
             url: frontmatter.url,
             author: frontmatter.author,
             published: frontmatter.published,
+            note_type: frontmatter.note_type,
+            extra: frontmatter.extra,
         }
     }
 }
@@ -129,6 +230,10 @@ struct Link {
     regex: String,
     resolution: String,
     folder: String,
+    #[serde(default)]
+    content: Option<ContentOptions>,
+    #[serde(default)]
+    archive: Option<ArchiveOptions>,
 }
 
 #[derive(Debug)]
@@ -138,14 +243,85 @@ struct VideoMetadata {
     title: String,
     description: String,
     channel: String,
+    channel_id: String,
     published_at: String,
+    thumbnail: String,
+    tags: Vec<String>,
+    channel_url: String,
+    /// False when the metadata came from oEmbed and the watch page, i.e. there is no usable API key.
+    from_data_api: bool,
+}
+
+#[derive(Debug, Default, Clone)]
+struct WebpageData {
+    title: String,
+    summary: String,
+    authors: Vec<String>,
+    published: String,
+    image: String,
+    tags: Vec<String>,
+    publisher: String,
+    site_name: String,
+}
+
+impl WebpageData {
+    /// Structured data is authored by the publisher, so it beats both scraped markup and the LLM.
+    fn apply_jsonld(&mut self, structured: &jsonld::JsonLd) {
+        if !structured.headline.is_empty() {
+            self.title = structured.headline.clone();
+        }
+        if self.summary.is_empty() {
+            self.summary = structured.description.clone();
+        }
+        let authors = authors::clean(&structured.authors);
+        if !authors.is_empty() {
+            self.authors = authors;
+        }
+        if !structured.published.is_empty() {
+            self.published = structured.published.clone();
+        }
+        if !structured.image.is_empty() {
+            self.image = structured.image.clone();
+        }
+        if !structured.publisher.is_empty() {
+            self.publisher = structured.publisher.clone();
+        }
+        self.tags.extend(structured.keywords.iter().cloned());
+        self.tags.sort();
+        self.tags.dedup();
+    }
+}
+
+/// What a site-specific handler extracted; `write_capture` turns it into a note.
+#[derive(Debug, Default)]
+struct Capture {
+    title: String,
+    description: String,
+    embed_code: String,
+    authors: Vec<String>,
     tags: Vec<String>,
+    published: String,
+    extra: Vec<(String, String)>,
+    /// Frontmatter `type:`; empty keeps the configured default.
+    note_type: String,
+    /// The URL as bookmarked, recorded when it differs from the note's `url`.
+    source_url: String,
 }
 
 enum LinkType {
     Shorts(String, String, usize, usize),
     YouTube(String, String, usize, usize),
-    WebLink(String, String, usize, usize),
+    Video(VideoPlatform, String, String, usize, usize),
+    GitHub(String, String),
+    Paper(String, String),
+    Discussion(String, String),
+    Wikipedia(String, String, usize, usize),
+    Social(String, String),
+    StackExchange(String, String),
+    Package(String, String),
+    Book(String, String, usize, usize),
+    Podcast(String, String, usize, usize),
+    WebLink(String, String, usize, usize, Link),
 }
 
 impl LinkType {
@@ -153,19 +329,60 @@ impl LinkType {
         debug!("LinkType::from_url: url={} config={:?}", url, config);
         let mut default_link = None;
 
+        // `youtube::parse_url` decides what is a YouTube URL, whatever older configs' regexes cover;
+        // the `shorts` and `youtube` rules only supply the folder and resolution.
+        if let Some(youtube_url) = youtube::parse_url(url) {
+            let shorts = youtube_url.kind == YouTubeKind::Shorts && config.links.iter().any(|link| link.name == "shorts");
+            let name = if shorts { "shorts" } else { "youtube" };
+            if let Some(link) = config.links.iter().find(|link| link.name == name) {
+                let (width, height) = get_resolution(name, config)?;
+                return Ok(if shorts {
+                    Self::Shorts(url.to_string(), link.folder.clone(), width, height)
+                } else {
+                    Self::YouTube(url.to_string(), link.folder.clone(), width, height)
+                });
+            }
+        }
+
         for link in &config.links {
             let regex = Regex::new(&link.regex)?;
             debug!("before regex.is_match on {:?}", url);
             if regex.is_match(url) {
                 let (width, height) = get_resolution(&link.name, config)?;
                 if link.name == "default" {
-                    default_link = Some(Self::WebLink(url.to_string(), link.folder.clone(), width, height));
+                    default_link = Some(Self::WebLink(url.to_string(), link.folder.clone(), width, height, link.clone()));
+                    continue;
+                }
+                // Site paths like github.com/topics/rust fit the rule's regex but aren't repositories.
+                if matches!(link.name.as_str(), "shorts" | "youtube")
+                    || (link.name == "peertube" && video::parse_peertube_url(url).is_none())
+                    || (link.name == "github" && github::parse_url(url).is_none())
+                    || (link.name == "paper" && paper::parse_url(url).is_none())
+                    || (discussion::RULE_NAMES.contains(&link.name.as_str()) && discussion::parse_url(url).is_none())
+                    || (link.name == "wikipedia" && wikipedia::parse_url(url).is_none())
+                    || (social::RULE_NAMES.contains(&link.name.as_str()) && social::parse_url(url).is_none())
+                    || (link.name == "stackexchange" && stackexchange::parse_url(url).is_none())
+                    || (link.name == "package" && package::parse_url(url).is_none())
+                    || (link.name == "book" && book::parse_url(url).is_none())
+                    || (link.name == "podcast" && podcast::parse_url(url).is_none())
+                {
                     continue;
                 }
                 return Ok(match link.name.as_str() {
-                    "shorts" => Self::Shorts(url.to_string(), link.folder.clone(), width, height),
-                    "youtube" => Self::YouTube(url.to_string(), link.folder.clone(), width, height),
-                    _ => Self::WebLink(url.to_string(), link.folder.clone(), width, height),
+                    "vimeo" => Self::Video(VideoPlatform::Vimeo, url.to_string(), link.folder.clone(), width, height),
+                    "twitch" => Self::Video(VideoPlatform::Twitch, url.to_string(), link.folder.clone(), width, height),
+                    "dailymotion" => Self::Video(VideoPlatform::Dailymotion, url.to_string(), link.folder.clone(), width, height),
+                    "peertube" => Self::Video(VideoPlatform::PeerTube, url.to_string(), link.folder.clone(), width, height),
+                    "github" => Self::GitHub(url.to_string(), link.folder.clone()),
+                    "paper" => Self::Paper(url.to_string(), link.folder.clone()),
+                    "hackernews" | "reddit" => Self::Discussion(url.to_string(), link.folder.clone()),
+                    "wikipedia" => Self::Wikipedia(url.to_string(), link.folder.clone(), width, height),
+                    "mastodon" | "bluesky" | "x" => Self::Social(url.to_string(), link.folder.clone()),
+                    "stackexchange" => Self::StackExchange(url.to_string(), link.folder.clone()),
+                    "package" => Self::Package(url.to_string(), link.folder.clone()),
+                    "book" => Self::Book(url.to_string(), link.folder.clone(), width, height),
+                    "podcast" => Self::Podcast(url.to_string(), link.folder.clone(), width, height),
+                    _ => Self::WebLink(url.to_string(), link.folder.clone(), width, height, link.clone()),
                 });
             }
         }
@@ -176,6 +393,36 @@ impl LinkType {
 
         Err(eyre!("Invalid URL format"))
     }
+
+    /// The URL a note will be stored under and the rule's folder.
+    fn url_and_folder(&self) -> (&str, &str) {
+        match self {
+            Self::Shorts(url, folder, ..)
+            | Self::YouTube(url, folder, ..)
+            | Self::Video(_, url, folder, ..)
+            | Self::GitHub(url, folder)
+            | Self::Paper(url, folder)
+            | Self::Discussion(url, folder)
+            | Self::Wikipedia(url, folder, ..)
+            | Self::Social(url, folder)
+            | Self::StackExchange(url, folder)
+            | Self::Package(url, folder)
+            | Self::Book(url, folder, ..)
+            | Self::Podcast(url, folder, ..)
+            | Self::WebLink(url, folder, ..) => (url, folder),
+        }
+    }
+
+    /// The `default` rule's web link, for URLs a specific rule matched but its handler can't take.
+    fn default_link(url: &str, config: &Config) -> Result<Self> {
+        let link = config
+            .links
+            .iter()
+            .find(|link| link.name == "default")
+            .ok_or_else(|| eyre!("Link type 'default' not found in config"))?;
+        let (width, height) = get_resolution(&link.name, config)?;
+        Ok(Self::WebLink(url.to_string(), link.folder.clone(), width, height, link.clone()))
+    }
 }
 
 fn expanduser<T: AsRef<str>>(path: T) -> PathBuf {
@@ -183,9 +430,9 @@ fn expanduser<T: AsRef<str>>(path: T) -> PathBuf {
     PathBuf::from(expanded_path_str.into_owned())
 }
 
-fn today() -> (String, String, String) {
-    debug!("today");
-    let now = Utc::now().with_timezone(&*TIMEZONE);
+fn today(timezone: Tz) -> (String, String, String) {
+    debug!("today: timezone={}", timezone);
+    let now = Utc::now().with_timezone(&timezone);
 
     let date_format = StrftimeItems::new("%Y-%m-%d");
     let day_format = StrftimeItems::new("%a");
@@ -213,7 +460,7 @@ fn get_resolution(link_name: &str, config: &Config) -> Result<(usize, usize)> {
             .get(resolution_key)
             .copied()
             .ok_or_else(|| eyre!("Resolution not found for shorts")),
-        "youtube" | _ => RESOLUTIONS
+        _ => RESOLUTIONS
             .get(resolution_key)
             .copied()
             .ok_or_else(|| eyre!("Resolution not found for {}", link_name)),
@@ -230,13 +477,13 @@ fn get_field_value<T: Clone + Default + PartialEq>(incoming: &T, default: &T, fa
     }
 }
 
-fn format_frontmatter(frontmatter: &Frontmatter, url: &str, author: &str, tags: &[String], published: &str, default_frontmatter: &Frontmatter) -> Frontmatter {
+fn format_frontmatter(frontmatter: &Frontmatter, url: &str, authors: &[String], tags: &[String], published: &str, default_frontmatter: &Frontmatter, timezone: Tz) -> Frontmatter {
     debug!(
-        "format_frontmatter: frontmatter={:?} url={} author={} tags={:?}",
-        frontmatter, url, author, tags
+        "format_frontmatter: frontmatter={:?} url={} authors={:?} tags={:?}",
+        frontmatter, url, authors, tags
     );
 
-    let (current_date, current_day, current_time) = today();
+    let (current_date, current_day, current_time) = today(timezone);
 
     let date = get_field_value(&frontmatter.date, &default_frontmatter.date, current_date);
     let day = get_field_value(&frontmatter.day, &default_frontmatter.day, current_day);
@@ -248,8 +495,9 @@ fn format_frontmatter(frontmatter: &Frontmatter, url: &str, author: &str, tags:
     tags.sort();
 
     let url = get_field_value(&frontmatter.url, &default_frontmatter.url, url.to_string());
-    let author = get_field_value(&frontmatter.author, &default_frontmatter.author, author.to_string());
-    let published = get_field_value(&frontmatter.published, &default_frontmatter.published, published.to_string());
+    let author = get_field_value(&frontmatter.author, &default_frontmatter.author, authors.to_vec());
+    let published = dates::normalize(published, Utc::now().with_timezone(&timezone));
+    let published = get_field_value(&frontmatter.published, &default_frontmatter.published, published);
 
     Frontmatter {
         date,
@@ -259,6 +507,8 @@ fn format_frontmatter(frontmatter: &Frontmatter, url: &str, author: &str, tags:
         url,
         author,
         published,
+        note_type: default_frontmatter.note_type.clone(),
+        extra: Vec::new(),
     }
 }
 
@@ -282,45 +532,37 @@ fn sanitize_filename(title: &str) -> Result<String> {
     Ok(re.replace_all(&sanitized_title, " ").to_string())
 }
 
-fn extract_video_id(url: &str) -> Result<String> {
-    debug!("extract_video_id: url={}", url);
-    let pattern = Regex::new(r#"(youtu\.be/|youtube\.com/(watch\?(.*&)?v=|(embed|v|shorts)/))([^?&">]+)"#)
-        .map_err(|e| eyre!("Failed to compile regex: {}", e))?;
-
-    pattern
-        .captures(url)
-        .and_then(|caps| caps.get(5))
-        .map(|m| m.as_str().to_string())
-        .ok_or_else(|| eyre!("Failed to extract video ID from URL"))
+fn wikilink(name: &str) -> Result<String> {
+    Ok(format!("[[{}]]", sanitize_filename(name)?))
 }
 
-async fn fetch_video_metadata(api_key: &str, video_id: &str) -> Result<VideoMetadata> {
-    debug!("fetch_video_metadata: api_key={} video_id={}", api_key, video_id);
-    let url = format!(
-        "https://www.googleapis.com/youtube/v3/videos?id={video_id}&part=snippet&key={api_key}"
-    );
+fn quote_yaml(value: &str) -> String {
+    let needs_quotes = value.starts_with(['[', '{', '&', '*', '!', '|', '>', '\'', '"', '%', '@', '`'])
+        || value.contains(": ")
+        || value.contains(" #");
+    if needs_quotes {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        value.to_string()
+    }
+}
 
-    let response = reqwest::get(&url).await?.json::<serde_json::Value>().await?;
+fn vault_folder(vault_path: &Path, folder: Option<String>) -> Result<PathBuf> {
+    debug!("vault_folder: vault_path={} folder={:?}", vault_path.display(), folder);
+    let vault_path_str = vault_path
+        .to_str()
+        .ok_or_else(|| eyre!("Failed to convert vault path to string"))?;
+    let vault_path_expanded = expanduser(vault_path_str);
 
-    if response["items"].as_array().unwrap_or(&Vec::new()).is_empty() {
-        return Err(eyre!("Video metadata not found for video_id={}", video_id));
-    }
+    let folder_path = if let Some(folder) = folder {
+        vault_path_expanded.join(folder)
+    } else {
+        vault_path_expanded
+    };
 
-    let snippet = &response["items"][0]["snippet"];
-    Ok(VideoMetadata {
-        id: video_id.to_string(),
-        title: snippet["title"].as_str().unwrap_or_default().to_string(),
-        description: snippet["description"].as_str().unwrap_or_default().to_string(),
-        channel: snippet["channelTitle"].as_str().unwrap_or_default().to_string(),
-        published_at: snippet["publishedAt"].as_str().unwrap_or_default().to_string(),
-        tags: snippet["tags"]
-            .as_array()
-            .unwrap_or(&Vec::new())
-            .iter()
-            .filter_map(|tag| tag.as_str())
-            .map(String::from)
-            .collect(),
-    })
+    std::fs::create_dir_all(&folder_path)
+        .map_err(|e| eyre!("Failed to create directory: {:?} with error {}", folder_path, e))?;
+    Ok(folder_path)
 }
 
 fn generate_embed_code(video_id: &str, width: usize, height: usize) -> String {
@@ -328,8 +570,12 @@ fn generate_embed_code(video_id: &str, width: usize, height: usize) -> String {
         "generate_embed_code: video_id={} width={} height={}",
         video_id, width, height
     );
+    generate_iframe_embed_code(&format!("https://www.youtube.com/embed/{video_id}"), width, height)
+}
+
+fn generate_iframe_embed_code(src: &str, width: usize, height: usize) -> String {
     format!(
-        "<iframe width=\"{width}\" height=\"{height}\" src=\"https://www.youtube.com/embed/{video_id}\" frameborder=\"0\" allowfullscreen></iframe>"
+        "<iframe width=\"{width}\" height=\"{height}\" src=\"{src}\" frameborder=\"0\" allowfullscreen></iframe>"
     )
 }
 
@@ -339,6 +585,29 @@ fn generate_image_embed_code(img_url: &str, width: usize, height: usize) -> Stri
     )
 }
 
+async fn download_image_embed_code(img_url: &str, width: usize, height: usize, config: &Config) -> Option<String> {
+    if !config.attachments.download || img_url.is_empty() {
+        return None;
+    }
+    match attachments::download(img_url, config).await {
+        Ok(file_name) => Some(attachments::generate_embed_code(&file_name, width, height)),
+        Err(e) => {
+            error!("Failed to download image {}: {:?}", img_url, e);
+            None
+        }
+    }
+}
+
+/// Embeds a remote image, preferring a downloaded attachment when that's enabled.
+async fn image_embed_code(img_url: &str, width: usize, height: usize, config: &Config) -> String {
+    if img_url.is_empty() {
+        return String::new();
+    }
+    download_image_embed_code(img_url, width, height, config)
+        .await
+        .unwrap_or_else(|| generate_image_embed_code(img_url, width, height))
+}
+
 fn extract_title_and_tags(text: &str) -> Result<(String, Vec<String>)> {
     let mut modified_text = text.to_string();
 
@@ -362,38 +631,125 @@ fn extract_title_and_tags(text: &str) -> Result<(String, Vec<String>)> {
     Ok((title.trim().to_string(), tags))
 }
 
+/// Finds a note in `folder_path` whose frontmatter `url:` matches, so recapturing a page
+/// (or a redirect/AMP variant resolving to the same canonical URL) doesn't create a duplicate.
+/// Only the head of each note is read, since folders like the vault root can hold thousands of them.
+fn find_note_by_url(folder_path: &Path, url: &str) -> Option<PathBuf> {
+    if url.is_empty() {
+        return None;
+    }
+    notes(folder_path).into_iter().find(|path| {
+        let mut head = Vec::new();
+        std::fs::File::open(path)
+            .and_then(|file| file.take(FRONTMATTER_SCAN_BYTES).read_to_end(&mut head))
+            .is_ok_and(|_| {
+                let head = String::from_utf8_lossy(&head);
+                // Content notes written next to a bookmark carry its URL too.
+                frontmatter_value(&head, "url").is_some_and(|value| value == url)
+                    && frontmatter_value(&head, "type").is_none_or(|note_type| note_type != "content")
+            })
+    })
+}
+
+/// Returned when the folder already has a note for the URL; notes may have been edited since, so it's left alone.
+#[derive(Debug)]
+struct AlreadyBookmarked(PathBuf);
+
+impl std::fmt::Display for AlreadyBookmarked {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Already bookmarked in {}", self.0.display())
+    }
+}
+
+impl std::error::Error for AlreadyBookmarked {}
+
+/// Fails with `AlreadyBookmarked` before any fetching, so a duplicate costs nothing and leaves no stray files.
+fn ensure_not_bookmarked(url: &str, folder: Option<String>, config: &Config) -> Result<()> {
+    match find_note_by_url(&vault_folder(&config.vault, folder)?, url) {
+        Some(existing) => Err(AlreadyBookmarked(existing).into()),
+        None => Ok(()),
+    }
+}
+
+/// A path for a new note named `title` that doesn't clobber an existing one: `Title.md`, then `Title (2).md`, ...
+fn new_note_path(folder_path: &Path, title: &str) -> Result<PathBuf> {
+    new_file_path(folder_path, title, "md")
+}
+
+/// `new_note_path` for any extension.
+fn new_file_path(folder_path: &Path, title: &str, extension: &str) -> Result<PathBuf> {
+    let file_name = sanitize_filename(title)?;
+    let path = folder_path.join(format!("{file_name}.{extension}"));
+    if !path.exists() {
+        return Ok(path);
+    }
+    (2..)
+        .map(|n| folder_path.join(format!("{file_name} ({n}).{extension}")))
+        .find(|path| !path.exists())
+        .ok_or_else(|| eyre!("No free file name for {}", file_name))
+}
+
+fn notes(folder_path: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(folder_path)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Reads a scalar `key: value` from a note's frontmatter, undoing `quote_yaml`.
+fn frontmatter_value(note: &str, key: &str) -> Option<String> {
+    let prefix = format!("{key}: ");
+    note.lines()
+        .skip(1)
+        .take_while(|line| *line != "---")
+        .find_map(|line| line.strip_prefix(&prefix))
+        .map(|value| {
+            let value = value.trim();
+            match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+                Some(quoted) => quoted.replace("\\\"", "\"").replace("\\\\", "\\"),
+                None => value.to_string(),
+            }
+        })
+}
+
+/// Records where the capture started when it differs from the URL stored in `url:`.
+fn push_source_url(frontmatter: &mut Frontmatter, source_url: &str) {
+    if !source_url.is_empty() && frontmatter.url != source_url {
+        frontmatter.extra.push(("source_url".to_string(), source_url.to_string()));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn create_markdown_file(
     title: &str,
     description: &str,
     embed_code: &str,
     url: &str,
-    author: &str,
+    authors: &[String],
     tags: &[String],
     vault_path: &Path,
     folder: Option<String>,
     frontmatter: &Frontmatter,
 ) -> Result<()> {
-    info!("create_markdown_file: title={} description={} embed_code={} url={} author={} tags={:?} vault_path={} folder={:?} frontmatter={:?}", title, description, embed_code, url, author, tags, vault_path.display(), folder, frontmatter);
-    let vault_path_str = vault_path
-        .to_str()
-        .ok_or_else(|| eyre!("Failed to convert vault path to string"))?;
-    let vault_path_expanded = expanduser(vault_path_str);
-
-    let folder_path = if let Some(folder) = folder {
-        vault_path_expanded.join(folder)
-    } else {
-        vault_path_expanded
-    };
-
-    std::fs::create_dir_all(&folder_path)
-        .map_err(|e| eyre!("Failed to create directory: {:?} with error {}", folder_path, e))?;
+    info!("create_markdown_file: title={} description={} embed_code={} url={} authors={:?} tags={:?} vault_path={} folder={:?} frontmatter={:?}", title, description, embed_code, url, authors, tags, vault_path.display(), folder, frontmatter);
+    let folder_path = vault_folder(vault_path, folder)?;
 
-    let file_name = sanitize_filename(title)?;
-    let file_path = folder_path.join(file_name + ".md");
+    // Handlers check up front; this catches URLs that only became known during the capture.
+    if let Some(existing) = find_note_by_url(&folder_path, &frontmatter.url) {
+        return Err(AlreadyBookmarked(existing).into());
+    }
+    let file_path = new_note_path(&folder_path, title)?;
 
     info!("file_path={:?}", file_path);
 
-    let mut file = std::fs::File::create(&file_path)
+    let mut file = std::fs::File::options()
+        .write(true)
+        .create_new(true)
+        .open(&file_path)
         .map_err(|e| eyre!("Failed to create markdown file: {:?} with error {}", file_path, e))?;
 
     writeln!(file, "---")?;
@@ -405,9 +761,15 @@ fn create_markdown_file(
         writeln!(file, "  - {}", sanitize_tag(tag))?;
     }
     writeln!(file, "url: {}", frontmatter.url)?;
-    writeln!(file, "author: {}", frontmatter.author)?;
+    writeln!(file, "author:")?;
+    for author in &frontmatter.author {
+        writeln!(file, "  - {}", quote_yaml(author))?;
+    }
     writeln!(file, "published: {}", frontmatter.published)?;
-    writeln!(file, "type: link")?;
+    for (key, value) in &frontmatter.extra {
+        writeln!(file, "{}: {}", key, quote_yaml(value))?;
+    }
+    writeln!(file, "type: {}", if frontmatter.note_type.is_empty() { "link" } else { &frontmatter.note_type })?;
     writeln!(file, "---\n")?;
 
     write!(
@@ -418,39 +780,27 @@ fn create_markdown_file(
     .map_err(|e| eyre!("Failed to write to markdown file: {}", e))
 }
 
-async fn download_webpage(url: &str) -> Result<String> {
-    let response = reqwest::get(url).await?;
-    let content = response.text().await?;
-    Ok(content)
-}
-
-fn extract_data_from_webpage(content: &str) -> Result<(String, String, String, String, String, Vec<String>)> {
-    let document = Html::parse_document(content);
+fn extract_data_from_webpage(document: &Html, structured: Option<&jsonld::JsonLd>) -> Result<WebpageData> {
 
     let title_selector = Selector::parse("title").map_err(|e| eyre!("Failed to compile selector: {}", e))?;
-    let title = document
+    let html_title = document
         .select(&title_selector)
         .next()
         .map_or(String::new(), |e| e.inner_html());
+    let page_meta = opengraph::extract(document);
+    let title = page_meta.best_title(&html_title);
 
     let meta_selector = Selector::parse("meta[name='description']").map_err(|e| eyre!("Failed to compile selector: {}", e))?;
     let summary = document
         .select(&meta_selector)
         .next()
         .map_or(String::new(), |e| e.value().attr("content").unwrap_or("").to_string());
+    let summary = page_meta.best_description(&summary);
 
-    let author_selectors = [
-        Selector::parse("meta[name='author']").map_err(|e| eyre!("Failed to compile selector: {}", e))?,
-        Selector::parse("meta[property='article:author']").map_err(|e| eyre!("Failed to compile selector: {}", e))?,
-        Selector::parse(".author").map_err(|e| eyre!("Failed to compile selector: {}", e))?,
-        Selector::parse("[itemprop='author']").map_err(|e| eyre!("Failed to compile selector: {}", e))?,
-    ];
-    let mut author = "Not specified".to_string();
-    for selector in &author_selectors {
-        if let Some(element) = document.select(selector).next() {
-            author = element.text().collect::<Vec<_>>().join(" ");
-            break;
-        }
+    let mut authors = authors::extract(document);
+    if authors.is_empty() {
+        // `twitter:creator` is a handle; without the `@` it still beats having no author.
+        authors = authors::clean([page_meta.twitter_creator.trim_start_matches('@')]);
     }
 
     let published_selectors = [
@@ -470,11 +820,7 @@ fn extract_data_from_webpage(content: &str) -> Result<(String, String, String, S
         }
     }
 
-    let image_selector = Selector::parse("meta[property='og:image']").map_err(|e| eyre!("Failed to compile selector: {}", e))?;
-    let image = document
-        .select(&image_selector)
-        .next()
-        .map_or(String::new(), |e| e.value().attr("content").unwrap_or("").to_string());
+    let image = page_meta.best_image();
 
     let tag_selectors = [
         Selector::parse("meta[name='keywords']").map_err(|e| eyre!("Failed to compile selector: {}", e))?,
@@ -484,7 +830,7 @@ fn extract_data_from_webpage(content: &str) -> Result<(String, String, String, S
         Selector::parse("[itemprop='keywords']").map_err(|e| eyre!("Failed to compile selector: {}", e))?,
     ];
 
-    let mut tags = Vec::new();
+    let mut tags = page_meta.article_tags.clone();
 
     for selector in &tag_selectors {
         if let Some(element) = document.select(selector).next() {
@@ -496,18 +842,41 @@ fn extract_data_from_webpage(content: &str) -> Result<(String, String, String, S
         }
     }
 
-    tags = tags.into_iter().filter(|tag| !tag.is_empty()).collect();
+    tags.retain(|tag| !tag.is_empty());
     tags.sort();
     tags.dedup();
 
-    Ok((title, summary, author, published, image, tags))
+    let mut data = WebpageData {
+        title,
+        summary,
+        authors,
+        published,
+        image,
+        tags,
+        publisher: String::new(),
+        site_name: page_meta.site_name(),
+    };
+    if let Some(structured) = structured {
+        debug!("Found JSON-LD {} for webpage", structured.kind);
+        data.apply_jsonld(structured);
+    }
+
+    Ok(data)
 }
 
-async fn fetch_and_summarize_url_with_chatgpt(
-    url: &str,
-) -> Result<(String, String, String, String, String, Vec<String>)> {
-    let content = download_webpage(url).await?;
-    let (title, summary, author, published, image, tags) = extract_data_from_webpage(&content)?;
+async fn summarize_url_with_chatgpt(url: &str, document: &Html, structured: Option<&jsonld::JsonLd>, timezone: Tz) -> Result<WebpageData> {
+    let extracted = extract_data_from_webpage(document, structured)?;
+    let WebpageData {
+        title,
+        summary,
+        authors,
+        published,
+        image,
+        tags,
+        publisher,
+        site_name,
+    } = extracted;
+    let author = authors.join(", ");
 
     debug!("Fetched content from URL: {}", url);
     debug!(
@@ -537,7 +906,6 @@ async fn fetch_and_summarize_url_with_chatgpt(
 
     debug!("Prompt for ChatGPT: {}", prompt);
 
-    let client = reqwest::Client::new();
     let request_body = json!({
         "model": "gpt-3.5-turbo",
         "messages": [
@@ -546,18 +914,19 @@ async fn fetch_and_summarize_url_with_chatgpt(
         ]
     });
 
-    let response = client
-        .post("https://api.openai.com/v1/chat/completions")
-        .header("Authorization", format!("Bearer {}", CHATGPT_API_KEY.as_str()))
-        .header("Content-Type", "application/json")
-        .json(&request_body)
-        .send()
-        .await?;
+    let response = http::send(
+        http::client()
+            .post("https://api.openai.com/v1/chat/completions")
+            .header("Authorization", format!("Bearer {}", CHATGPT_API_KEY.as_str()))
+            .header("Content-Type", "application/json")
+            .json(&request_body),
+    )
+    .await?;
 
     debug!("Response from ChatGPT: {:?}", response);
 
     if response.status() == 200 {
-        let response_body = response.json::<serde_json::Value>().await?;
+        let response_body: serde_json::Value = http::json(response).await?;
         let assistant_reply = &response_body["choices"][0]["message"]["content"];
 
         debug!("Assistant reply: {:?}", assistant_reply);
@@ -582,22 +951,38 @@ async fn fetch_and_summarize_url_with_chatgpt(
                     Ok(parsed) => {
                         debug!("Parsed JSON from assistant reply: {:?}", parsed);
 
-                        let (current_date, _, _) = today();
+                        let (current_date, _, _) = today(timezone);
                         let title = parsed["title"]
                             .as_str()
                             .unwrap_or(&format!("No Title {current_date}"))
                             .to_string();
                         let summary = parsed["summary"].as_str().unwrap_or_default().to_string();
-                        let author = parsed["author"].as_str().unwrap_or_default().to_string();
+                        let authors = match &parsed["author"] {
+                            serde_json::Value::Array(values) => authors::clean(values.iter().filter_map(|value| value.as_str())),
+                            value => authors::clean(value.as_str()),
+                        };
                         let published = parsed["published"].as_str().unwrap_or_default().to_string();
                         let image = parsed["main_image_url"].as_str().unwrap_or_default().to_string();
                         let tags = parsed["tags"].as_array().map_or_else(Vec::new, |arr| {
                             arr.iter().filter_map(|tag| tag.as_str().map(String::from)).collect()
                         });
 
-                        debug!("Final extracted data - Title: {}, Summary: {}, Author: {}, Published: {}, Image: {}, Tags: {:?}", title, summary, author, published, image, tags);
-
-                        Ok((title, summary, author, published, image, tags))
+                        debug!("Final extracted data - Title: {}, Summary: {}, Authors: {:?}, Published: {}, Image: {}, Tags: {:?}", title, summary, authors, published, image, tags);
+
+                        let mut data = WebpageData {
+                            title,
+                            summary,
+                            authors,
+                            published,
+                            image,
+                            tags,
+                            publisher: publisher.clone(),
+                            site_name: site_name.clone(),
+                        };
+                        if let Some(structured) = structured {
+                            data.apply_jsonld(structured);
+                        }
+                        Ok(data)
                     }
                     Err(e) => {
                         error!("Failed to parse extracted JSON string: {}", e);
@@ -607,7 +992,7 @@ async fn fetch_and_summarize_url_with_chatgpt(
             },
         )
     } else {
-        let error_text = response.text().await?;
+        let error_text = http::text(response).await?;
         error!("Error response from ChatGPT: {}", error_text);
         Err(eyre!("Error: {}", error_text))
     }
@@ -625,15 +1010,26 @@ async fn handle_shorts_url(
         "handle_shorts_url: url={}, title={} folder={:?}, width={} height={}, config={:?}",
         url, title, folder, width, height, config
     );
-    let video_id = extract_video_id(url)?;
-    let metadata = fetch_video_metadata(&YOUTUBE_API_KEY, &video_id).await?;
-    let embed_code = generate_embed_code(&video_id, width, height);
+    let video_id = youtube::resolve_video_id(url).await?;
+    let metadata = youtube::fetch_video_metadata(YOUTUBE_API_KEY.as_deref(), &video_id).await?;
+    let mut embed_code = generate_embed_code(&video_id, width, height);
+    if let Some(thumbnail_embed) = download_image_embed_code(&metadata.thumbnail, width, height, config).await {
+        embed_code = format!("{embed_code}\n\n{thumbnail_embed}");
+    }
 
     let (metadata_title, metadata_tags) = extract_title_and_tags(&metadata.title)?;
     let (title, tags) = extract_title_and_tags(title)?;
 
     let final_title = if title.is_empty() { metadata_title } else { title };
 
+    let authors = match youtube::update_channel_note(YOUTUBE_API_KEY.as_deref(), &metadata, &final_title, config).await {
+        Ok(channel_link) => channel_link.into_iter().collect(),
+        Err(e) => {
+            error!("Failed to update channel note: {:?}", e);
+            vec![metadata.channel.clone()]
+        }
+    };
+
     let mut combined_tags: HashSet<String> = HashSet::new();
     combined_tags.extend(tags);
     combined_tags.extend(metadata_tags);
@@ -643,10 +1039,11 @@ async fn handle_shorts_url(
     let frontmatter = config.frontmatter.merge(&format_frontmatter(
         &config.frontmatter,
         url,
-        &metadata.channel,
+        &authors,
         &combined_tags,
         &metadata.published_at,
         &config.frontmatter,
+        config.timezone,
     ));
 
     create_markdown_file(
@@ -654,7 +1051,7 @@ async fn handle_shorts_url(
         &metadata.description,
         &embed_code,
         url,
-        &metadata.channel,
+        &authors,
         &combined_tags,
         &config.vault,
         folder,
@@ -674,15 +1071,26 @@ async fn handle_youtube_url(
         "handle_youtube_url: url={}, title={} folder={:?}, width={} height={}, config={:?}",
         url, title, folder, width, height, config
     );
-    let video_id = extract_video_id(url)?;
-    let metadata = fetch_video_metadata(&YOUTUBE_API_KEY, &video_id).await?;
-    let embed_code = generate_embed_code(&video_id, width, height);
+    let video_id = youtube::resolve_video_id(url).await?;
+    let metadata = youtube::fetch_video_metadata(YOUTUBE_API_KEY.as_deref(), &video_id).await?;
+    let mut embed_code = generate_embed_code(&video_id, width, height);
+    if let Some(thumbnail_embed) = download_image_embed_code(&metadata.thumbnail, width, height, config).await {
+        embed_code = format!("{embed_code}\n\n{thumbnail_embed}");
+    }
 
     let (metadata_title, metadata_tags) = extract_title_and_tags(&metadata.title)?;
     let (title, tags) = extract_title_and_tags(title)?;
 
     let final_title = if title.is_empty() { metadata_title } else { title };
 
+    let authors = match youtube::update_channel_note(YOUTUBE_API_KEY.as_deref(), &metadata, &final_title, config).await {
+        Ok(channel_link) => channel_link.into_iter().collect(),
+        Err(e) => {
+            error!("Failed to update channel note: {:?}", e);
+            vec![metadata.channel.clone()]
+        }
+    };
+
     let mut combined_tags: HashSet<String> = HashSet::new();
     combined_tags.extend(tags);
     combined_tags.extend(metadata_tags);
@@ -692,10 +1100,11 @@ async fn handle_youtube_url(
     let frontmatter = config.frontmatter.merge(&format_frontmatter(
         &config.frontmatter,
         url,
-        &metadata.channel,
+        &authors,
         &combined_tags,
         &metadata.published_at,
         &config.frontmatter,
+        config.timezone,
     ));
 
     create_markdown_file(
@@ -703,7 +1112,7 @@ async fn handle_youtube_url(
         &metadata.description,
         &embed_code,
         url,
-        &metadata.channel,
+        &authors,
         &combined_tags,
         &config.vault,
         folder,
@@ -711,7 +1120,8 @@ async fn handle_youtube_url(
     )
 }
 
-async fn handle_weblink_url(
+async fn handle_video_url(
+    metadata: video::PlatformVideo,
     url: &str,
     title: &str,
     folder: Option<String>,
@@ -720,15 +1130,476 @@ async fn handle_weblink_url(
     config: &Config,
 ) -> Result<()> {
     info!(
-        "handle_weblink_url: url={}, title={} folder={:?}, width={} height={}, config={:?}",
+        "handle_video_url: url={}, title={} folder={:?}, width={} height={}, config={:?}",
         url, title, folder, width, height, config
     );
-    let (fetched_title, summary, author, published, image, fetched_tags) =
-        fetch_and_summarize_url_with_chatgpt(url).await?;
-    let embed_code = if image.is_empty() {
-        String::new()
+    let mut embed_code = generate_iframe_embed_code(&metadata.embed_url, width, height);
+    if let Some(thumbnail_embed) = download_image_embed_code(&metadata.thumbnail, width, height, config).await {
+        embed_code = format!("{embed_code}\n\n{thumbnail_embed}");
+    }
+
+    let (metadata_title, metadata_tags) = extract_title_and_tags(&metadata.title)?;
+    let (title, tags) = extract_title_and_tags(title)?;
+
+    let final_title = if title.is_empty() { metadata_title } else { title };
+
+    let mut combined_tags: HashSet<String> = HashSet::new();
+    combined_tags.extend(tags);
+    combined_tags.extend(metadata_tags);
+    combined_tags.extend(metadata.tags);
+    let combined_tags: Vec<String> = combined_tags.into_iter().collect();
+    let authors = if metadata.channel.is_empty() { Vec::new() } else { vec![metadata.channel.clone()] };
+
+    let mut frontmatter = config.frontmatter.merge(&format_frontmatter(
+        &config.frontmatter,
+        url,
+        &authors,
+        &combined_tags,
+        &metadata.published,
+        &config.frontmatter,
+        config.timezone,
+    ));
+    if let Some(duration) = metadata.duration {
+        frontmatter.extra.push(("duration".to_string(), video::format_duration(duration)));
+    }
+
+    create_markdown_file(
+        &final_title,
+        &metadata.description,
+        &embed_code,
+        url,
+        &authors,
+        &combined_tags,
+        &config.vault,
+        folder,
+        &frontmatter,
+    )
+}
+
+/// Writes a note from a handler's `Capture`, letting the bookmark's own title and tags win.
+fn write_capture(capture: Capture, url: &str, title: &str, folder: Option<String>, config: &Config) -> Result<()> {
+    let (metadata_title, metadata_tags) = extract_title_and_tags(&capture.title)?;
+    let (title, tags) = extract_title_and_tags(title)?;
+
+    let final_title = if title.is_empty() { metadata_title } else { title };
+
+    let mut combined_tags: HashSet<String> = HashSet::new();
+    combined_tags.extend(tags);
+    combined_tags.extend(metadata_tags);
+    combined_tags.extend(capture.tags);
+    let combined_tags: Vec<String> = combined_tags.into_iter().collect();
+    let authors: Vec<String> = capture.authors.into_iter().filter(|author| !author.is_empty()).collect();
+
+    let mut frontmatter = config.frontmatter.merge(&format_frontmatter(
+        &config.frontmatter,
+        url,
+        &authors,
+        &combined_tags,
+        &capture.published,
+        &config.frontmatter,
+        config.timezone,
+    ));
+    push_source_url(&mut frontmatter, &capture.source_url);
+    frontmatter.extra.extend(capture.extra);
+    if !capture.note_type.is_empty() {
+        frontmatter.note_type = capture.note_type;
+    }
+
+    create_markdown_file(
+        &final_title,
+        &capture.description,
+        &capture.embed_code,
+        url,
+        &authors,
+        &combined_tags,
+        &config.vault,
+        folder,
+        &frontmatter,
+    )
+}
+
+async fn handle_github_url(url: &str, title: &str, folder: Option<String>, config: &Config) -> Result<()> {
+    info!("handle_github_url: url={}, title={} folder={:?}", url, title, folder);
+    let github_url = github::parse_url(url).ok_or_else(|| eyre!("Unsupported GitHub URL: {}", url))?;
+    let capture = github::fetch(&config.github, &github_url, config.timezone).await?;
+    write_capture(capture, url, title, folder, config)
+}
+
+async fn handle_paper_url(url: &str, title: &str, folder: Option<String>, config: &Config) -> Result<()> {
+    info!("handle_paper_url: url={}, title={} folder={:?}", url, title, folder);
+    let id = paper::parse_url(url).ok_or_else(|| eyre!("No arXiv id or DOI in URL: {}", url))?;
+    let paper = paper::fetch(&id).await?;
+    let mut capture = paper::capture(&paper, &config.papers)?;
+    capture.authors = author_links(capture.authors, config)?;
+    write_capture(capture, url, title, folder, config)
+}
+
+async fn handle_discussion_url(url: &str, title: &str, folder: Option<String>, config: &Config) -> Result<()> {
+    info!("handle_discussion_url: url={}, title={} folder={:?}", url, title, folder);
+    let thread = discussion::parse_url(url).ok_or_else(|| eyre!("Not a discussion thread URL: {}", url))?;
+    let capture = discussion::fetch(&thread, &config.discussions).await?;
+    write_capture(capture, url, title, folder, config)
+}
+
+async fn handle_wikipedia_url(
+    url: &str,
+    title: &str,
+    folder: Option<String>,
+    width: usize,
+    height: usize,
+    config: &Config,
+) -> Result<()> {
+    info!("handle_wikipedia_url: url={}, title={} folder={:?}", url, title, folder);
+    let article_url = wikipedia::parse_url(url).ok_or_else(|| eyre!("Not a Wikipedia article URL: {}", url))?;
+    let article = wikipedia::fetch(&article_url).await?;
+    let mut capture = wikipedia::capture(&article);
+    capture.embed_code = image_embed_code(&article.thumbnail, width, height, config).await;
+    write_capture(capture, url, title, folder, config)
+}
+
+async fn handle_social_url(url: &str, title: &str, folder: Option<String>, config: &Config) -> Result<()> {
+    info!("handle_social_url: url={}, title={} folder={:?}", url, title, folder);
+    let post_url = social::parse_url(url).ok_or_else(|| eyre!("Not a social media post URL: {}", url))?;
+    // The rule matches `/@user/<digits>` on any host, so the server may not speak Mastodon or ActivityPub at all.
+    let (post, ancestors) = match social::fetch(&post_url).await {
+        Ok(thread) => thread,
+        Err(e) => {
+            error!("Failed to fetch post {}: {:?}", url, e);
+            return handle_as_weblink(url, title, folder, config).await;
+        }
+    };
+    write_capture(social::capture(&post, &ancestors), url, title, folder, config)
+}
+
+/// Saves `url` through the `default` rule's web link handler, keeping the caller's folder.
+async fn handle_as_weblink(url: &str, title: &str, folder: Option<String>, config: &Config) -> Result<()> {
+    info!("handle_as_weblink: url={}, title={} folder={:?}", url, title, folder);
+    let LinkType::WebLink(url, _, width, height, link) = LinkType::default_link(url, config)? else {
+        unreachable!("default_link always returns a web link")
+    };
+    handle_weblink_url(&url, title, folder, width, height, &link, config).await
+}
+
+async fn handle_stackexchange_url(url: &str, title: &str, folder: Option<String>, config: &Config) -> Result<()> {
+    info!("handle_stackexchange_url: url={}, title={} folder={:?}", url, title, folder);
+    let post = stackexchange::parse_url(url).ok_or_else(|| eyre!("Not a Stack Exchange question URL: {}", url))?;
+    let capture = stackexchange::fetch(&post).await?;
+    write_capture(capture, url, title, folder, config)
+}
+
+async fn handle_package_url(url: &str, title: &str, folder: Option<String>, config: &Config) -> Result<()> {
+    info!("handle_package_url: url={}, title={} folder={:?}", url, title, folder);
+    let package_url = package::parse_url(url).ok_or_else(|| eyre!("Not a package registry URL: {}", url))?;
+    let capture = package::fetch(&package_url).await?;
+    write_capture(capture, url, title, folder, config)
+}
+
+async fn handle_book_url(
+    url: &str,
+    book_url: &BookUrl,
+    title: &str,
+    folder: Option<String>,
+    width: usize,
+    height: usize,
+    config: &Config,
+) -> Result<()> {
+    info!("handle_book_url: url={}, book_url={:?}, title={} folder={:?}", url, book_url, title, folder);
+    let book = book::fetch(book_url).await?;
+    write_capture(book_capture(&book, width, height, config).await?, url, title, folder, config)
+}
+
+async fn book_capture(book: &book::Book, width: usize, height: usize, config: &Config) -> Result<Capture> {
+    let mut capture = book::capture(book, &config.books);
+    capture.authors = author_links(capture.authors, config)?;
+    capture.embed_code = image_embed_code(&book.cover, width, height, config).await;
+    Ok(capture)
+}
+
+async fn handle_podcast_url(
+    url: &str,
+    title: &str,
+    folder: Option<String>,
+    width: usize,
+    height: usize,
+    config: &Config,
+) -> Result<()> {
+    info!("handle_podcast_url: url={}, title={} folder={:?}", url, title, folder);
+    let apple = podcast::parse_url(url).ok_or_else(|| eyre!("Not an Apple Podcasts episode URL: {}", url))?;
+    let episode = match podcast::fetch_apple(&apple).await {
+        Ok(episode) => episode,
+        Err(e) => {
+            // The lookup only lists a show's most recent episodes; older ones are kept as plain web links.
+            info!("handle_podcast_url: {:?}; saving {} as a web link", e, url);
+            return handle_as_weblink(url, title, folder, config).await;
+        }
+    };
+    write_capture(podcast_capture(&episode, width, height, config).await, url, title, folder, config)
+}
+
+async fn podcast_capture(episode: &podcast::Episode, width: usize, height: usize, config: &Config) -> Capture {
+    let mut capture = podcast::capture(episode);
+    let cover = image_embed_code(&episode.image, width, height, config).await;
+    if !cover.is_empty() {
+        capture.embed_code = format!("{cover}\n\n{}", capture.embed_code);
+    }
+    capture
+}
+
+fn author_links(authors: Vec<String>, config: &Config) -> Result<Vec<String>> {
+    if config.author_wikilinks {
+        authors::wikilinks(&authors)
+    } else {
+        Ok(authors)
+    }
+}
+
+fn title_from_url(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .and_then(|u| u.path_segments().and_then(|mut segments| segments.rfind(|s| !s.is_empty()).map(str::to_string)))
+        .map(|name| name.rsplit_once('.').map_or(name.clone(), |(stem, _)| stem.to_string()))
+        .map(|name| name.replace(['-', '_'], " "))
+        .unwrap_or_else(|| url.to_string())
+}
+
+fn content_max_length(link: &Link) -> usize {
+    link.content
+        .as_ref()
+        .map_or(content::DEFAULT_MAX_LENGTH, |options| options.max_length)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_pdf_payload(
+    url: &str,
+    source_url: &str,
+    title: &str,
+    folder: Option<String>,
+    bytes: &[u8],
+    link: &Link,
+    config: &Config,
+) -> Result<()> {
+    info!("handle_pdf_payload: url={}, title={} folder={:?}", url, title, folder);
+    let pdf = pdf::extract(bytes)?;
+    let (title, tags) = extract_title_and_tags(title)?;
+    let final_title = [title, pdf.title.clone(), title_from_url(url)]
+        .into_iter()
+        .find(|title| !title.is_empty())
+        .unwrap_or_default();
+
+    let embed_code = if config.attachments.download {
+        let file_name = attachments::store(bytes, "application/pdf", url, config)?;
+        format!("![[{file_name}]]")
     } else {
-        generate_image_embed_code(&image, width, height)
+        format!("[PDF]({url})")
+    };
+
+    let authors = author_links(authors::clean([&pdf.author]), config)?;
+    let mut frontmatter = config.frontmatter.merge(&format_frontmatter(
+        &config.frontmatter,
+        url,
+        &authors,
+        &tags,
+        &pdf.created,
+        &config.frontmatter,
+        config.timezone,
+    ));
+    push_source_url(&mut frontmatter, source_url);
+    frontmatter.extra.push(("pages".to_string(), pdf.pages.to_string()));
+
+    create_markdown_file(
+        &final_title,
+        &content::truncate(&pdf.text, content_max_length(link)),
+        &embed_code,
+        url,
+        &authors,
+        &tags,
+        &config.vault,
+        folder,
+        &frontmatter,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_image_payload(
+    url: &str,
+    source_url: &str,
+    title: &str,
+    folder: Option<String>,
+    content_type: &str,
+    bytes: &[u8],
+    width: usize,
+    height: usize,
+    config: &Config,
+) -> Result<()> {
+    info!("handle_image_payload: url={}, title={} folder={:?} content_type={}", url, title, folder, content_type);
+    let file_name = attachments::store(bytes, content_type, url, config)?;
+    let embed_code = attachments::generate_embed_code(&file_name, width, height);
+    let (title, tags) = extract_title_and_tags(title)?;
+    let final_title = if title.is_empty() { title_from_url(url) } else { title };
+
+    let mut frontmatter = config.frontmatter.merge(&format_frontmatter(
+        &config.frontmatter,
+        url,
+        &[],
+        &tags,
+        "",
+        &config.frontmatter,
+        config.timezone,
+    ));
+    push_source_url(&mut frontmatter, source_url);
+
+    create_markdown_file(&final_title, "", &embed_code, url, &[], &tags, &config.vault, folder, &frontmatter)
+}
+
+/// Downloads the page can't describe get a note with just the link.
+fn handle_file_payload(url: &str, source_url: &str, title: &str, folder: Option<String>, content_type: &str, config: &Config) -> Result<()> {
+    info!("handle_file_payload: url={}, title={} folder={:?} content_type={}", url, title, folder, content_type);
+    let (title, tags) = extract_title_and_tags(title)?;
+    let final_title = if title.is_empty() { title_from_url(url) } else { title };
+
+    let mut frontmatter = config.frontmatter.merge(&format_frontmatter(
+        &config.frontmatter,
+        url,
+        &[],
+        &tags,
+        "",
+        &config.frontmatter,
+        config.timezone,
+    ));
+    push_source_url(&mut frontmatter, source_url);
+    frontmatter.extra.push(("content_type".to_string(), content_type.to_string()));
+
+    create_markdown_file(&final_title, &format!("<{url}>"), "", url, &[], &tags, &config.vault, folder, &frontmatter)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_text_payload(
+    url: &str,
+    source_url: &str,
+    title: &str,
+    folder: Option<String>,
+    language: &str,
+    text: &str,
+    link: &Link,
+    config: &Config,
+) -> Result<()> {
+    info!("handle_text_payload: url={}, title={} folder={:?} language={}", url, title, folder, language);
+    let (title, tags) = extract_title_and_tags(title)?;
+    let final_title = if title.is_empty() { title_from_url(url) } else { title };
+    let description = content::code_block(language, &content::truncate(text.trim_end(), content_max_length(link)));
+
+    let mut frontmatter = config.frontmatter.merge(&format_frontmatter(
+        &config.frontmatter,
+        url,
+        &[],
+        &tags,
+        "",
+        &config.frontmatter,
+        config.timezone,
+    ));
+    push_source_url(&mut frontmatter, source_url);
+
+    create_markdown_file(&final_title, &description, "", url, &[], &tags, &config.vault, folder, &frontmatter)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn handle_weblink_url(
+    url: &str,
+    title: &str,
+    folder: Option<String>,
+    width: usize,
+    height: usize,
+    link: &Link,
+    config: &Config,
+) -> Result<()> {
+    info!(
+        "handle_weblink_url: url={}, title={} folder={:?}, width={} height={}, link={:?}, config={:?}",
+        url, title, folder, width, height, link, config
+    );
+    let source_url = url;
+    let download::Download { final_url, payload } = download::download(url).await?;
+    ensure_not_bookmarked(&final_url, folder.clone(), config)?;
+    let content = match payload {
+        Payload::Html(content) => content,
+        Payload::Pdf(bytes) => return handle_pdf_payload(&final_url, source_url, title, folder, &bytes, link, config),
+        Payload::Image { content_type, bytes } => {
+            return handle_image_payload(&final_url, source_url, title, folder, &content_type, &bytes, width, height, config)
+        }
+        Payload::Text { language, text } => {
+            return handle_text_payload(&final_url, source_url, title, folder, &language, &text, link, config)
+        }
+        Payload::File { content_type } => return handle_file_payload(&final_url, source_url, title, folder, &content_type, config),
+    };
+    let base = Url::parse(&final_url).map_err(|e| eyre!("Failed to parse URL: {}", e))?;
+    let document = Html::parse_document(&content);
+    let entities = jsonld::entities(&document);
+    let structured = jsonld::extract(&entities);
+    let url = opengraph::canonical_url(&document, &opengraph::extract(&document), &base)
+        .map_or(final_url, String::from);
+    let url = url.as_str();
+    debug!("handle_weblink_url: source_url={} canonical_url={}", source_url, url);
+    ensure_not_bookmarked(url, folder.clone(), config)?;
+    // Publisher pages don't share a URL shape, but they do tag themselves with their ISBN.
+    if let Some(isbn) = book::isbn_from_document(&document, &entities) {
+        // Open Library doesn't know every ISBN; the page itself is still worth keeping.
+        match book::fetch(&BookUrl::Isbn(isbn)).await {
+            Ok(book) => {
+                let mut capture = book_capture(&book, width, height, config).await?;
+                capture.source_url = source_url.to_string();
+                return write_capture(capture, url, title, folder, config);
+            }
+            Err(e) => debug!("handle_weblink_url: no book data: {:?}", e),
+        }
+    }
+    if let Some(recipe) = recipe::from_entities(&entities) {
+        info!("handle_weblink_url: found recipe {:?}", recipe.name);
+        let mut capture = recipe::capture(&recipe);
+        capture.authors = author_links(capture.authors, config)?;
+        capture.embed_code = image_embed_code(&recipe.image, width, height, config).await;
+        capture.source_url = source_url.to_string();
+        return write_capture(capture, url, title, folder, config);
+    }
+    if podcast::looks_like_episode(&document, &entities) {
+        if let Some(feed_url) = podcast::feed_url(&document, &base) {
+            match podcast::fetch_episode(&feed_url, &podcast::episode_match(&document, url)).await {
+                Ok(episode) => {
+                    let mut capture = podcast_capture(&episode, width, height, config).await;
+                    capture.source_url = source_url.to_string();
+                    return write_capture(capture, url, title, folder, config);
+                }
+                Err(e) => debug!("handle_weblink_url: not a podcast episode: {:?}", e),
+            }
+        }
+    }
+    let oembed = match oembed::endpoint(&config.oembed, url, &document, &base) {
+        Some(endpoint) => match oembed::fetch(&endpoint, url).await {
+            Ok(oembed) => oembed,
+            Err(e) => {
+                error!("Failed to fetch oEmbed data for {}: {:?}", url, e);
+                oembed::OEmbed::default()
+            }
+        },
+        None => oembed::OEmbed::default(),
+    };
+    let WebpageData {
+        title: fetched_title,
+        summary,
+        authors,
+        published,
+        image,
+        tags: fetched_tags,
+        publisher,
+        site_name,
+    } = summarize_url_with_chatgpt(url, &document, structured.as_ref(), config.timezone).await?;
+    // The provider's own title, author and player beat what the summary guessed from the page.
+    let fetched_title = if oembed.title.is_empty() { fetched_title } else { oembed.title.clone() };
+    let authors = if oembed.author_name.is_empty() { authors } else { vec![oembed.author_name.clone()] };
+    let embed_code = match oembed.embed_code() {
+        Some(embed_code) => embed_code,
+        None => {
+            let image = if oembed.image().is_empty() { &image } else { oembed.image() };
+            image_embed_code(image, width, height, config).await
+        }
     };
 
     let (metadata_title, metadata_tags) = extract_title_and_tags(&fetched_title)?;
@@ -741,22 +1612,57 @@ async fn handle_weblink_url(
     combined_tags.extend(metadata_tags);
     combined_tags.extend(fetched_tags);
     let combined_tags: Vec<String> = combined_tags.into_iter().collect();
+    let authors = author_links(authors, config)?;
 
-    let frontmatter = config.frontmatter.merge(&format_frontmatter(
+    let mut frontmatter = config.frontmatter.merge(&format_frontmatter(
         &config.frontmatter,
         url,
-        &author,
+        &authors,
         &combined_tags,
         &published,
         &config.frontmatter,
+        config.timezone,
     ));
+    push_source_url(&mut frontmatter, source_url);
+    if !site_name.is_empty() {
+        frontmatter.extra.push(("site_name".to_string(), site_name));
+    }
+    if !publisher.is_empty() {
+        frontmatter.extra.push(("publisher".to_string(), publisher));
+    }
+    if let Some(options) = &link.archive {
+        match archive::archive_page(&content, url, &final_title, &config.vault, options).await {
+            Ok(archive_link) => frontmatter.extra.push(("archive".to_string(), archive_link)),
+            Err(e) => error!("Failed to archive {}: {:?}", url, e),
+        }
+    }
+
+    let mut description = summary;
+    let discussions = discussion::find_discussions(config, &[url, source_url]);
+    if let Some(first) = discussions.first() {
+        frontmatter.extra.push(("discussion".to_string(), first.clone()));
+        let links = discussions.iter().map(|discussion| format!("- {discussion}")).collect::<Vec<_>>().join("\n");
+        description = format!("{description}\n\n## Discussion\n{links}");
+    }
+    if let Some(options) = &link.content {
+        let markdown = content::truncate(&content::extract_markdown(&content, url), options.max_length);
+        if markdown.is_empty() {
+            debug!("No main content found for {}", url);
+        } else {
+            let section = match options.mode {
+                ContentMode::Section => markdown,
+                ContentMode::Note => content::write_note(&final_title, url, &markdown, &config.vault, folder.clone())?,
+            };
+            description = format!("{description}\n\n## Content\n{section}");
+        }
+    }
 
     create_markdown_file(
         &final_title,
-        &summary,
+        &description,
         &embed_code,
         url,
-        &author,
+        &authors,
         &combined_tags,
         &config.vault,
         folder,
@@ -785,15 +1691,62 @@ async fn handle_url(url: &str, title: &str, folder: Option<String>, config: &Con
     );
     let url = remove_utm_source(url)?;
     debug!("utm_source removed url={}", url);
-    match LinkType::from_url(&url, config)? {
+    // A PeerTube video is fetched up front: that is the only way to tell an instance from any other
+    // site with /w/ paths, and the answer is reused for the note.
+    let mut video = None;
+    let link_type = match LinkType::from_url(&url, config)? {
+        LinkType::Video(VideoPlatform::PeerTube, url, default_folder, width, height) => {
+            match video::fetch_video(VideoPlatform::PeerTube, &url).await {
+                Ok(metadata) => {
+                    video = Some(metadata);
+                    LinkType::Video(VideoPlatform::PeerTube, url, default_folder, width, height)
+                }
+                Err(e) => {
+                    info!("handle_url: {} is not on a PeerTube instance, saving it as a web link: {:?}", url, e);
+                    LinkType::default_link(&url, config)?
+                }
+            }
+        }
+        link_type => link_type,
+    };
+    let (link_url, default_folder) = link_type.url_and_folder();
+    ensure_not_bookmarked(link_url, folder.clone().or(Some(default_folder.to_string())), config)?;
+    match link_type {
         LinkType::Shorts(url, default_folder, width, height) => {
             handle_shorts_url(&url, title, folder.or(Some(default_folder)), width, height, config).await
         }
         LinkType::YouTube(url, default_folder, width, height) => {
             handle_youtube_url(&url, title, folder.or(Some(default_folder)), width, height, config).await
         }
-        LinkType::WebLink(url, default_folder, width, height) => {
-            handle_weblink_url(&url, title, folder.or(Some(default_folder)), width, height, config).await
+        LinkType::Video(platform, url, default_folder, width, height) => {
+            let metadata = match video {
+                Some(metadata) => metadata,
+                None => video::fetch_video(platform, &url).await?,
+            };
+            handle_video_url(metadata, &url, title, folder.or(Some(default_folder)), width, height, config).await
+        }
+        LinkType::GitHub(url, default_folder) => handle_github_url(&url, title, folder.or(Some(default_folder)), config).await,
+        LinkType::Paper(url, default_folder) => handle_paper_url(&url, title, folder.or(Some(default_folder)), config).await,
+        LinkType::Discussion(url, default_folder) => {
+            handle_discussion_url(&url, title, folder.or(Some(default_folder)), config).await
+        }
+        LinkType::Wikipedia(url, default_folder, width, height) => {
+            handle_wikipedia_url(&url, title, folder.or(Some(default_folder)), width, height, config).await
+        }
+        LinkType::Social(url, default_folder) => handle_social_url(&url, title, folder.or(Some(default_folder)), config).await,
+        LinkType::StackExchange(url, default_folder) => {
+            handle_stackexchange_url(&url, title, folder.or(Some(default_folder)), config).await
+        }
+        LinkType::Package(url, default_folder) => handle_package_url(&url, title, folder.or(Some(default_folder)), config).await,
+        LinkType::Podcast(url, default_folder, width, height) => {
+            handle_podcast_url(&url, title, folder.or(Some(default_folder)), width, height, config).await
+        }
+        LinkType::Book(url, default_folder, width, height) => {
+            let book_url = book::parse_url(&url).ok_or_else(|| eyre!("Not a book URL: {}", url))?;
+            handle_book_url(&url, &book_url, title, folder.or(Some(default_folder)), width, height, config).await
+        }
+        LinkType::WebLink(url, default_folder, width, height, link) => {
+            handle_weblink_url(&url, title, folder.or(Some(default_folder)), width, height, &link, config).await
         }
     }
 }
@@ -806,6 +1759,10 @@ async fn bookmark(bookmark: web::Json<Bookmark>, config: web::Data<Config>) -> i
 
     match handle_url(&bookmark.url, &bookmark.title, bookmark.folder.clone(), &config).await {
         Ok(()) => HttpResponse::Ok().json(serde_json::json!({"status": "success"})),
+        Err(e) if e.is::<AlreadyBookmarked>() => {
+            info!("{}", e);
+            HttpResponse::Conflict().json(serde_json::json!({"status": "exists", "message": e.to_string()}))
+        }
         Err(e) => {
             error!("Failed to process bookmark: {:?}", e);
             HttpResponse::InternalServerError().json(serde_json::json!({"status": "error", "message": e.to_string()}))
@@ -855,6 +1812,7 @@ async fn main() -> Result<()> {
     info!("Starting server on port: {}", cli.port);
 
     let config = load_config(&cli.config)?;
+    http::init(&config.http)?;
 
     let server = HttpServer::new(move || {
         info!("Setting up the Actix app with CORS and services");
@@ -912,8 +1870,10 @@ mod tests {
                 "football-101".to_string(),
             ],
             url: "https://www.youtube.com/watch?v=7sgCH4U7rjU&t=32s".to_string(),
-            author: "Weekly Spiral".to_string(),
+            author: vec!["Weekly Spiral".to_string()],
             published: "2021-08-16T15:29:39Z".to_string(),
+            note_type: "link".to_string(),
+            extra: Vec::new(),
         };
 
         let expected_output = r#"---
@@ -933,7 +1893,8 @@ tags:
   - passing-game
   - weekly-spiral
 url: https://www.youtube.com/watch?v=7sgCH4U7rjU&t=32s
-author: Weekly Spiral
+author:
+  - Weekly Spiral
 published: 2021-08-16T15:29:39Z
 type: link
 ---"#;
@@ -945,6 +1906,7 @@ type: link
             &malformed_frontmatter.tags,
             &malformed_frontmatter.published,
             &config.frontmatter,
+            config.timezone,
         );
 
         let frontmatter_str = format!(
@@ -958,11 +1920,11 @@ type: link
         }
 
         let final_output = format!(
-            "{}{}url: {}\nauthor: {}\npublished: {}\ntype: link\n---",
+            "{}{}url: {}\nauthor:\n{}published: {}\ntype: link\n---",
             frontmatter_str,
             tags_str,
             formatted_frontmatter.url,
-            formatted_frontmatter.author,
+            formatted_frontmatter.author.iter().map(|author| format!("  - {author}\n")).collect::<String>(),
             formatted_frontmatter.published
         );
 
@@ -998,6 +1960,12 @@ type: link
             "https://www.youtube.com/watch?v=U3HndX2QnSo",
             "https://youtu.be/EkDxsQRbIwoA",
             "https://youtu.be/m7lnIdudEy8?si=VE-14Y1Sk93RdA5u",
+            "https://m.youtube.com/watch?v=y4evLICF8kk",
+            "https://music.youtube.com/watch?v=y4evLICF8kk",
+            "https://www.youtube.com/watch?feature=share&v=y4evLICF8kk",
+            "https://www.youtube.com/live/jfKfPfyJRdk",
+            "https://www.youtube-nocookie.com/embed/y4evLICF8kk",
+            "https://youtube.com/clip/UgkxU2HSeGL_NvmDJ-nQJrlLwllwMDBdGZFs",
         ];
 
         for url in urls {
@@ -1020,6 +1988,137 @@ type: link
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_video_platform_identification() -> Result<()> {
+        let config = load_test_config();
+
+        let urls = vec![
+            ("https://vimeo.com/76979871", VideoPlatform::Vimeo),
+            ("https://player.vimeo.com/video/76979871", VideoPlatform::Vimeo),
+            ("https://www.twitch.tv/videos/1234567890", VideoPlatform::Twitch),
+            ("https://clips.twitch.tv/FunnyClipSlug", VideoPlatform::Twitch),
+            ("https://www.dailymotion.com/video/x7tgad0", VideoPlatform::Dailymotion),
+            ("https://framatube.org/w/9c9de5e8-0a1e-484a-b099-e80766180a6d", VideoPlatform::PeerTube),
+            ("https://video.example.social/w/kkGMgK9ZtnKfYAgnEtQxbv", VideoPlatform::PeerTube),
+        ];
+        assert!(matches!(
+            LinkType::from_url("https://en.wikipedia.org/w/index.php?title=Rust", &config)?,
+            LinkType::WebLink(..)
+        ));
+
+        for (url, expected) in urls {
+            let link_type = LinkType::from_url(url, &config)?;
+            assert!(matches!(link_type, LinkType::Video(platform, ..) if platform == expected), "{}", url);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_youtube_routing_ignores_rule_regexes() -> Result<()> {
+        let mut config = load_test_config();
+        // A config written before the parser learned live, clip and nocookie URLs.
+        for link in config.links.iter_mut().filter(|link| link.name == "shorts" || link.name == "youtube") {
+            link.regex = r"https?://www\.youtube\.com/watch\?v=".to_string();
+        }
+        assert!(matches!(LinkType::from_url("https://www.youtube.com/live/jfKfPfyJRdk", &config)?, LinkType::YouTube(..)));
+        assert!(matches!(LinkType::from_url("https://m.youtube.com/shorts/FjkS5rjNq-A", &config)?, LinkType::Shorts(..)));
+        assert!(matches!(LinkType::from_url("https://www.youtube.com/watch?list=PL123", &config)?, LinkType::WebLink(..)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_github_identification() -> Result<()> {
+        let config = load_test_config();
+        for url in ["https://github.com/rust-lang/rust", "https://gist.github.com/someone/abc123"] {
+            assert!(matches!(LinkType::from_url(url, &config)?, LinkType::GitHub(..)), "{}", url);
+        }
+        assert!(matches!(LinkType::from_url("https://github.com/rust-lang", &config)?, LinkType::WebLink(..)));
+        assert!(matches!(LinkType::from_url("https://github.com/topics/rust", &config)?, LinkType::WebLink(..)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_paper_identification() -> Result<()> {
+        let config = load_test_config();
+        for url in ["https://arxiv.org/abs/1706.03762", "https://doi.org/10.1145/3290605.3300233"] {
+            assert!(matches!(LinkType::from_url(url, &config)?, LinkType::Paper(..)), "{}", url);
+        }
+        assert!(matches!(LinkType::from_url("https://arxiv.org/list/cs.CL/recent", &config)?, LinkType::WebLink(..)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_discussion_identification() -> Result<()> {
+        let config = load_test_config();
+        for url in ["https://news.ycombinator.com/item?id=8863", "https://www.reddit.com/r/rust/comments/abc123/title/"] {
+            assert!(matches!(LinkType::from_url(url, &config)?, LinkType::Discussion(..)), "{}", url);
+        }
+        assert!(matches!(LinkType::from_url("https://news.ycombinator.com/news", &config)?, LinkType::WebLink(..)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_wikipedia_identification() -> Result<()> {
+        let config = load_test_config();
+        let link_type = LinkType::from_url("https://fr.m.wikipedia.org/wiki/Paris", &config)?;
+        assert!(matches!(link_type, LinkType::Wikipedia(..)));
+        assert!(matches!(LinkType::from_url("https://en.wikipedia.org/w/index.php?title=Paris", &config)?, LinkType::WebLink(..)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_social_identification() -> Result<()> {
+        let config = load_test_config();
+        for url in [
+            "https://mastodon.social/@Gargron/109318821117356215",
+            "https://bsky.app/profile/jay.bsky.team/post/3k44deefqdk2g",
+            "https://x.com/jack/status/20",
+        ] {
+            assert!(matches!(LinkType::from_url(url, &config)?, LinkType::Social(..)), "{}", url);
+        }
+        assert!(matches!(LinkType::from_url("https://x.com/jack", &config)?, LinkType::WebLink(..)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stackexchange_identification() -> Result<()> {
+        let config = load_test_config();
+        for url in ["https://stackoverflow.com/questions/11227809/why", "https://math.stackexchange.com/a/12345/678"] {
+            assert!(matches!(LinkType::from_url(url, &config)?, LinkType::StackExchange(..)), "{}", url);
+        }
+        assert!(matches!(LinkType::from_url("https://stackoverflow.com/questions/tagged/rust", &config)?, LinkType::WebLink(..)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_package_identification() -> Result<()> {
+        let config = load_test_config();
+        for url in ["https://crates.io/crates/serde", "https://docs.rs/tokio/latest/tokio/", "https://www.npmjs.com/package/react", "https://pypi.org/project/requests/"] {
+            assert!(matches!(LinkType::from_url(url, &config)?, LinkType::Package(..)), "{}", url);
+        }
+        assert!(matches!(LinkType::from_url("https://crates.io/categories", &config)?, LinkType::WebLink(..)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_book_identification() -> Result<()> {
+        let config = load_test_config();
+        for url in ["https://www.amazon.com/dp/1718503105", "https://www.goodreads.com/book/show/5907.The_Hobbit", "https://openlibrary.org/isbn/9780261102217"] {
+            assert!(matches!(LinkType::from_url(url, &config)?, LinkType::Book(..)), "{}", url);
+        }
+        assert!(matches!(LinkType::from_url("https://www.amazon.com/gp/cart/view.html", &config)?, LinkType::WebLink(..)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_podcast_identification() -> Result<()> {
+        let config = load_test_config();
+        let link_type = LinkType::from_url("https://podcasts.apple.com/us/podcast/show/id1234567890?i=1000600000001", &config)?;
+        assert!(matches!(link_type, LinkType::Podcast(..)));
+        assert!(matches!(LinkType::from_url("https://podcasts.apple.com/us/podcast/show/id1234567890", &config)?, LinkType::WebLink(..)));
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_invalid_shorts_url_format() -> Result<()> {
         let config = load_test_config();
@@ -1064,29 +2163,95 @@ type: link
         let description = "A test video.";
         let embed_code = "<iframe...></iframe>";
         let url = "https://www.example.com";
-        let author = "Test Channel";
+        let authors = vec!["Test Channel".to_string()];
         let tags = vec![String::from("test")];
         let config = load_test_config();
+        let vault = std::env::temp_dir().join(format!("obsidian-bookmark-special-{}", std::process::id()));
+        let frontmatter = Frontmatter {
+            url: url.to_string(),
+            ..config.frontmatter.clone()
+        };
 
         let result = create_markdown_file(
             title,
             description,
             embed_code,
             url,
-            author,
+            &authors,
             &tags,
-            &config.vault,
+            &vault,
             Some("test_folder".to_string()),
-            &config.frontmatter,
+            &frontmatter,
         );
 
         assert!(
             result.is_ok(),
             "Failed to create markdown file with special characters in title"
         );
+        assert!(vault.join("test_folder/Test SpecialCharacters.md").exists());
+        let again = create_markdown_file(title, description, embed_code, url, &authors, &tags, &vault, Some("test_folder".to_string()), &frontmatter);
+        assert!(again.is_err_and(|e| e.is::<AlreadyBookmarked>()));
+        std::fs::remove_dir_all(&vault)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_data_prefers_jsonld() -> Result<()> {
+        let content = r#"<html><head>
+            <title>Article | Site Name</title>
+            <meta name="author" content="Site Staff">
+            <meta name="keywords" content="news">
+            <script type="application/ld+json">{"@type":"BlogPosting","headline":"Article","author":{"name":"Jane Doe"},
+                "datePublished":"2023-06-05","keywords":["rust"],"publisher":{"name":"Site Name"}}</script>
+            </head></html>"#;
+        let document = Html::parse_document(content);
+        let data = extract_data_from_webpage(&document, jsonld::extract(&jsonld::entities(&document)).as_ref())?;
+        assert_eq!(data.title, "Article");
+        assert_eq!(data.authors, vec!["Jane Doe".to_string()]);
+        assert_eq!(data.published, "2023-06-05");
+        assert_eq!(data.publisher, "Site Name");
+        assert_eq!(data.tags, vec!["news".to_string(), "rust".to_string()]);
         Ok(())
     }
 
+    #[test]
+    fn test_find_note_by_url() -> Result<()> {
+        let folder = std::env::temp_dir().join(format!("obsidian-bookmark-dedupe-{}", std::process::id()));
+        std::fs::create_dir_all(&folder)?;
+        std::fs::write(folder.join("Old Title - Content.md"), "---\nurl: https://example.com/story\ntype: content\n---\n")?;
+        std::fs::write(folder.join("Old Title.md"), "---\nurl: https://example.com/story\n---\n\nurl: https://example.com/other\n")?;
+        assert_eq!(find_note_by_url(&folder, "https://example.com/story"), Some(folder.join("Old Title.md")));
+        assert_eq!(find_note_by_url(&folder, "https://example.com/other"), None);
+        assert_eq!(find_note_by_url(&folder, ""), None);
+        assert_eq!(new_note_path(&folder, "Old Title")?, folder.join("Old Title (2).md"));
+        assert_eq!(new_note_path(&folder, "New Title")?, folder.join("New Title.md"));
+        std::fs::remove_dir_all(&folder)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_timezone() -> Result<()> {
+        let yaml = "vault: /tmp\nfrontmatter:\n  date:\n  day:\n  time:\n  tags:\n  url:\n  author:\n  published:\nlinks: []\n";
+        let config: Config = serde_yaml::from_str(yaml)?;
+        assert_eq!(config.timezone, chrono_tz::America::Los_Angeles);
+        let config: Config = serde_yaml::from_str(&format!("{yaml}timezone: Europe/Berlin\n"))?;
+        assert_eq!(config.timezone, chrono_tz::Europe::Berlin);
+        Ok(())
+    }
+
+    #[test]
+    fn test_title_from_url() {
+        assert_eq!(title_from_url("https://example.com/papers/attention-is_all.pdf"), "attention is all");
+        assert_eq!(title_from_url("https://example.com/"), "https://example.com/");
+    }
+
+    #[test]
+    fn test_quote_yaml() {
+        assert_eq!(quote_yaml("Weekly Spiral"), "Weekly Spiral");
+        assert_eq!(quote_yaml("[[Weekly Spiral]]"), "\"[[Weekly Spiral]]\"");
+        assert_eq!(quote_yaml("Rust: The \"Book\""), "\"Rust: The \\\"Book\\\"\"");
+    }
+
     #[test]
     fn test_extract_title_and_tags() -> Result<()> {
         let text = "(1) Test title with #tag1 and #tag2";
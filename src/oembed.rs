@@ -0,0 +1,208 @@
+use eyre::{eyre, Result};
+use lazy_static::lazy_static;
+use log::debug;
+use regex::Regex;
+use scraper::{Html, Selector};
+use serde::Deserialize;
+use url::Url;
+
+use crate::http;
+
+lazy_static! {
+    static ref SCRIPT_RE: Regex = Regex::new(r"(?is)<script\b[^>]*?(?:/>|>.*?</script\s*>)").expect("Invalid script regex");
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct OEmbedProvider {
+    /// Page URLs this provider answers for.
+    #[serde(deserialize_with = "compile_regex")]
+    pub regex: Regex,
+    pub endpoint: String,
+}
+
+fn compile_regex<'de, D>(deserializer: D) -> std::result::Result<Regex, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Regex::new(&String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct OEmbedOptions {
+    /// Follow `<link rel="alternate" type="application/json+oembed">` on pages no provider matches.
+    #[serde(default = "default_discover")]
+    pub discover: bool,
+    #[serde(default)]
+    pub providers: Vec<OEmbedProvider>,
+}
+
+fn default_discover() -> bool {
+    true
+}
+
+impl Default for OEmbedOptions {
+    fn default() -> Self {
+        OEmbedOptions {
+            discover: default_discover(),
+            providers: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Endpoint {
+    pub url: String,
+    /// From a configured provider rather than the page's own discovery link.
+    pub configured: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct OEmbed {
+    pub kind: String,
+    pub title: String,
+    pub author_name: String,
+    pub thumbnail_url: String,
+    /// The media itself for `photo` responses.
+    pub url: String,
+    /// Only kept when it can be trusted; see `fetch`.
+    pub html: String,
+}
+
+impl OEmbed {
+    /// The player or widget HTML to put at the top of the note, if the response has one.
+    pub fn embed_code(&self) -> Option<String> {
+        match self.kind.as_str() {
+            "video" | "rich" if !self.html.trim().is_empty() => Some(self.html.trim().to_string()),
+            _ => None,
+        }
+    }
+
+    /// The picture to show otherwise: the photo itself for `photo` responses, else the thumbnail.
+    /// Left to the caller to render so attachment downloads apply.
+    pub fn image(&self) -> &str {
+        match self.kind.as_str() {
+            "photo" if !self.url.is_empty() => &self.url,
+            _ => &self.thumbnail_url,
+        }
+    }
+}
+
+/// The oEmbed endpoint for `url`: a configured provider first, then the page's own discovery link.
+pub fn endpoint(options: &OEmbedOptions, url: &str, document: &Html, base: &Url) -> Option<Endpoint> {
+    if let Some(provider) = options.providers.iter().find(|provider| provider.regex.is_match(url)) {
+        return Url::parse_with_params(&provider.endpoint, &[("url", url), ("format", "json")])
+            .map(|endpoint| Endpoint {
+                url: endpoint.into(),
+                configured: true,
+            })
+            .ok();
+    }
+    if !options.discover {
+        return None;
+    }
+    let selector = Selector::parse("link[rel~='alternate'][type='application/json+oembed'][href]").expect("Invalid oEmbed selector");
+    document
+        .select(&selector)
+        .filter_map(|link| base.join(link.value().attr("href")?.trim()).ok())
+        .map(|endpoint| Endpoint {
+            url: endpoint.into(),
+            configured: false,
+        })
+        .next()
+}
+
+fn str_field(value: &serde_json::Value) -> String {
+    value.as_str().unwrap_or_default().trim().to_string()
+}
+
+fn parse(response: &serde_json::Value) -> OEmbed {
+    OEmbed {
+        kind: str_field(&response["type"]),
+        title: str_field(&response["title"]),
+        author_name: str_field(&response["author_name"]),
+        thumbnail_url: str_field(&response["thumbnail_url"]),
+        url: str_field(&response["url"]),
+        html: SCRIPT_RE.replace_all(&str_field(&response["html"]), "").trim().to_string(),
+    }
+}
+
+fn host(url: &str) -> Option<String> {
+    Url::parse(url).ok()?.host_str().map(|host| host.trim_start_matches("www.").to_string())
+}
+
+/// Discovery links are on by default, and sites like WordPress point them at themselves, embedding their own
+/// page; their `html` is only kept for videos hosted somewhere else. Other pages fall back to the thumbnail.
+fn trusted_html(endpoint: &Endpoint, page_url: &str, oembed: &OEmbed) -> bool {
+    endpoint.configured || (oembed.kind == "video" && host(&endpoint.url) != host(page_url))
+}
+
+pub async fn fetch(endpoint: &Endpoint, page_url: &str) -> Result<OEmbed> {
+    debug!("fetch: endpoint={:?} page_url={}", endpoint, page_url);
+    let response = http::get(&endpoint.url).await?;
+    if !response.status().is_success() {
+        return Err(eyre!("oEmbed request to {} failed with status {}", endpoint.url, response.status()));
+    }
+    let mut oembed = parse(&http::json(response).await?);
+    if !trusted_html(endpoint, page_url, &oembed) {
+        oembed.html.clear();
+    }
+    Ok(oembed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_oembed_endpoint() -> Result<()> {
+        let base = Url::parse("https://example.com/posts/1")?;
+        let document = Html::parse_document(
+            r#"<link rel="alternate" type="application/json+oembed" href="/oembed?url=https%3A%2F%2Fexample.com%2Fposts%2F1&format=json">"#,
+        );
+        let mut options = OEmbedOptions::default();
+        assert_eq!(
+            endpoint(&options, base.as_str(), &document, &base),
+            Some(Endpoint {
+                url: "https://example.com/oembed?url=https%3A%2F%2Fexample.com%2Fposts%2F1&format=json".to_string(),
+                configured: false,
+            })
+        );
+        options.providers.push(OEmbedProvider {
+            regex: Regex::new(r"https?://example\.com/posts/")?,
+            endpoint: "https://provider.example/oembed".to_string(),
+        });
+        assert_eq!(
+            endpoint(&options, base.as_str(), &document, &base),
+            Some(Endpoint {
+                url: "https://provider.example/oembed?url=https%3A%2F%2Fexample.com%2Fposts%2F1&format=json".to_string(),
+                configured: true,
+            })
+        );
+        options.providers.clear();
+        options.discover = false;
+        assert_eq!(endpoint(&options, base.as_str(), &document, &base), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_oembed_embed_code() {
+        let rich = parse(&json!({"type": "rich", "title": "Track", "author_name": "Band", "html": "<iframe src=\"https://w.soundcloud.com/player\"></iframe>\n"}));
+        assert_eq!(rich.embed_code().as_deref(), Some("<iframe src=\"https://w.soundcloud.com/player\"></iframe>"));
+        assert_eq!(rich.author_name, "Band");
+        let photo = parse(&json!({"type": "photo", "title": "Sunset", "url": "https://live.staticflickr.com/1.jpg", "thumbnail_url": "https://t/1.jpg"}));
+        assert_eq!(photo.embed_code(), None);
+        assert_eq!(photo.image(), "https://live.staticflickr.com/1.jpg");
+        assert_eq!(rich.image(), "");
+        assert_eq!(parse(&json!({"type": "link", "title": "Page"})).embed_code(), None);
+
+        let page = "https://blog.example/2024/post";
+        let discovered = |url: &str| Endpoint { url: url.to_string(), configured: false };
+        let wordpress = parse(&json!({"type": "rich", "html": "<blockquote>Post</blockquote><script src=\"https://blog.example/embed.js\"></script>"}));
+        assert_eq!(wordpress.html, "<blockquote>Post</blockquote>");
+        assert!(!trusted_html(&discovered("https://blog.example/wp-json/oembed/1.0/embed"), page, &wordpress));
+        let video = parse(&json!({"type": "video", "html": "<iframe src=\"https://player.example/1\"></iframe>"}));
+        assert!(!trusted_html(&discovered("https://www.blog.example/oembed"), page, &video));
+        assert!(trusted_html(&discovered("https://player.example/oembed"), page, &video));
+    }
+}
@@ -0,0 +1,198 @@
+use scraper::{Html, Selector};
+use url::Url;
+
+/// OpenGraph and Twitter Card fields. Sites disagree on whether these live in
+/// `property` or `name`, so both attributes are read.
+#[derive(Debug, Default, Clone)]
+pub struct PageMeta {
+    pub og_title: String,
+    pub og_description: String,
+    pub og_site_name: String,
+    pub og_type: String,
+    pub og_url: String,
+    pub og_image: String,
+    pub article_tags: Vec<String>,
+    pub twitter_title: String,
+    pub twitter_description: String,
+    pub twitter_image: String,
+    pub twitter_site: String,
+    pub twitter_creator: String,
+}
+
+fn first_non_empty(values: &[&str]) -> String {
+    values
+        .iter()
+        .map(|value| value.trim())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default()
+        .to_string()
+}
+
+impl PageMeta {
+    pub fn best_title(&self, html_title: &str) -> String {
+        let html_title = strip_site_name(html_title, &self.og_site_name);
+        first_non_empty(&[&self.og_title, &self.twitter_title, &html_title])
+    }
+
+    pub fn best_description(&self, meta_description: &str) -> String {
+        first_non_empty(&[&self.og_description, &self.twitter_description, meta_description])
+    }
+
+    pub fn best_image(&self) -> String {
+        first_non_empty(&[&self.og_image, &self.twitter_image])
+    }
+
+    /// The human site name, falling back to the Twitter handle when there's no `og:site_name`.
+    pub fn site_name(&self) -> String {
+        first_non_empty(&[&self.og_site_name, &self.twitter_site])
+    }
+}
+
+/// Removes a trailing " | Site Name" (or " - ", " — ", " · ") from a `<title>`.
+pub fn strip_site_name(title: &str, site_name: &str) -> String {
+    let title = title.trim();
+    if site_name.is_empty() {
+        return title.to_string();
+    }
+    for separator in [" | ", " - ", " — ", " – ", " · ", " :: "] {
+        if let Some(stripped) = title.strip_suffix(&format!("{separator}{site_name}")) {
+            return stripped.trim().to_string();
+        }
+    }
+    title.to_string()
+}
+
+pub fn extract(document: &Html) -> PageMeta {
+    let selector = Selector::parse("meta[content]").expect("Invalid meta selector");
+    let mut meta = PageMeta::default();
+
+    for element in document.select(&selector) {
+        let element = element.value();
+        let Some(key) = element.attr("property").or_else(|| element.attr("name")) else {
+            continue;
+        };
+        let content = element.attr("content").unwrap_or_default().trim().to_string();
+        if content.is_empty() {
+            continue;
+        }
+
+        // First occurrence wins for single-valued fields, matching how crawlers read them.
+        let field = match key.to_lowercase().as_str() {
+            "og:title" => &mut meta.og_title,
+            "og:description" => &mut meta.og_description,
+            "og:site_name" => &mut meta.og_site_name,
+            "og:type" => &mut meta.og_type,
+            "og:url" => &mut meta.og_url,
+            "og:image" | "og:image:url" | "og:image:secure_url" => &mut meta.og_image,
+            "twitter:title" => &mut meta.twitter_title,
+            "twitter:description" => &mut meta.twitter_description,
+            "twitter:image" | "twitter:image:src" => &mut meta.twitter_image,
+            "twitter:site" => &mut meta.twitter_site,
+            "twitter:creator" => &mut meta.twitter_creator,
+            "article:tag" => {
+                meta.article_tags.push(content);
+                continue;
+            }
+            _ => continue,
+        };
+        if field.is_empty() {
+            *field = content;
+        }
+    }
+
+    meta
+}
+
+/// `m.example.com` and `amp.example.com` are the same site as `www.example.com`.
+fn site_host(url: &Url) -> Option<&str> {
+    let host = url.host_str()?;
+    Some(["www.", "m.", "amp."].iter().find_map(|prefix| host.strip_prefix(prefix)).unwrap_or(host))
+}
+
+/// The URL the publisher considers authoritative: `<link rel="canonical">`,
+/// then `og:url`. Relative values are resolved against `base`. Templates often
+/// point these at the homepage or another site, so only a non-root URL on the
+/// same site is accepted.
+pub fn canonical_url(document: &Html, meta: &PageMeta, base: &Url) -> Option<Url> {
+    let selector = Selector::parse("link[rel][href]").expect("Invalid link selector");
+    let canonical = document
+        .select(&selector)
+        .filter(|element| {
+            element
+                .value()
+                .attr("rel")
+                .is_some_and(|rel| rel.split_whitespace().any(|rel| rel.eq_ignore_ascii_case("canonical")))
+        })
+        .filter_map(|element| element.value().attr("href"))
+        .map(str::trim)
+        .find(|href| !href.is_empty());
+
+    canonical
+        .into_iter()
+        .chain(Some(meta.og_url.as_str()).filter(|og_url| !og_url.is_empty()))
+        .filter_map(|href| base.join(href).ok())
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+        .find(|url| site_host(url) == site_host(base) && url.path() != "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_opengraph_and_twitter() {
+        let html = r#"<html><head>
+            <title>Article | Site Name</title>
+            <meta property="og:site_name" content="Site Name">
+            <meta property="og:type" content="article">
+            <meta property="og:description" content="OG description">
+            <meta property="og:image" content="https://example.com/og.jpg">
+            <meta property="og:image" content="https://example.com/og-2.jpg">
+            <meta property="article:tag" content="rust">
+            <meta property="article:tag" content="parsing">
+            <meta name="twitter:title" content="Twitter Title">
+            <meta name="twitter:site" content="@site">
+            </head></html>"#;
+        let meta = extract(&Html::parse_document(html));
+        assert_eq!(meta.best_title("Article | Site Name"), "Twitter Title");
+        assert_eq!(meta.best_description("meta description"), "OG description");
+        assert_eq!(meta.best_image(), "https://example.com/og.jpg");
+        assert_eq!(meta.site_name(), "Site Name");
+        assert_eq!(meta.og_type, "article");
+        assert_eq!(meta.article_tags, vec!["rust".to_string(), "parsing".to_string()]);
+    }
+
+    #[test]
+    fn test_strip_site_name() {
+        assert_eq!(strip_site_name("Article | Site Name", "Site Name"), "Article");
+        assert_eq!(strip_site_name("Article - Site Name", "Site Name"), "Article");
+        assert_eq!(strip_site_name("Article | Other", "Site Name"), "Article | Other");
+        assert_eq!(strip_site_name(" Article ", ""), "Article");
+    }
+
+    #[test]
+    fn test_canonical_url() {
+        let base = Url::parse("https://example.com/amp/story?utm_medium=x").expect("valid url");
+        let html = r#"<html><head>
+            <meta property="og:url" content="https://example.com/og">
+            <link rel="Canonical" href="/story">
+            </head></html>"#;
+        let document = Html::parse_document(html);
+        let canonical = canonical_url(&document, &extract(&document), &base).map(String::from);
+        assert_eq!(canonical.as_deref(), Some("https://example.com/story"));
+
+        let document = Html::parse_document(r#"<meta property="og:url" content="https://example.com/og">"#);
+        let canonical = canonical_url(&document, &extract(&document), &base).map(String::from);
+        assert_eq!(canonical.as_deref(), Some("https://example.com/og"));
+
+        let document = Html::parse_document(r#"<link rel="canonical" href="javascript:void(0)">"#);
+        assert_eq!(canonical_url(&document, &extract(&document), &base), None);
+
+        let document = Html::parse_document(r#"<link rel="canonical" href="https://example.com/"><meta property="og:url" content="https://m.example.com/story">"#);
+        let canonical = canonical_url(&document, &extract(&document), &base).map(String::from);
+        assert_eq!(canonical.as_deref(), Some("https://m.example.com/story"));
+
+        let document = Html::parse_document(r#"<link rel="canonical" href="https://other.example/story">"#);
+        assert_eq!(canonical_url(&document, &extract(&document), &base), None);
+    }
+}
@@ -0,0 +1,301 @@
+use eyre::Result;
+use log::{debug, error};
+use url::Url;
+
+use crate::{http, Capture};
+
+#[derive(Debug, PartialEq)]
+pub enum PackageUrl {
+    Crate(String),
+    Npm(String),
+    PyPi(String),
+}
+
+#[derive(Debug, Default)]
+struct Package {
+    registry: &'static str,
+    name: String,
+    version: String,
+    description: String,
+    license: String,
+    repository: String,
+    homepage: String,
+    keywords: Vec<String>,
+    downloads: Option<u64>,
+    /// What `downloads` counts, e.g. "all time".
+    downloads_period: &'static str,
+    published: String,
+    registry_url: String,
+}
+
+pub fn parse_url(url: &str) -> Option<PackageUrl> {
+    let parsed = Url::parse(url).ok()?;
+    let host = parsed.host_str()?.trim_start_matches("www.");
+    let segments: Vec<&str> = parsed.path_segments()?.filter(|s| !s.is_empty()).collect();
+
+    match (host, segments.as_slice()) {
+        ("crates.io", ["crates", name, ..]) | ("docs.rs", ["crate", name, ..]) => Some(PackageUrl::Crate(name.to_string())),
+        ("docs.rs", [name, ..]) if !matches!(*name, "about" | "releases" | "-") => Some(PackageUrl::Crate(name.to_string())),
+        ("npmjs.com", ["package", scope, name, ..]) if scope.starts_with('@') => Some(PackageUrl::Npm(format!("{scope}/{name}"))),
+        ("npmjs.com", ["package", name, ..]) => Some(PackageUrl::Npm(name.to_string())),
+        ("pypi.org", ["project", name, ..]) => Some(PackageUrl::PyPi(name.to_string())),
+        _ => None,
+    }
+}
+
+fn str_field(value: &serde_json::Value) -> String {
+    value.as_str().unwrap_or_default().to_string()
+}
+
+/// Registries store `git+https://github.com/a/b.git` and the like; notes want a browsable link.
+fn repository_link(repository: &str) -> String {
+    let repository = repository.trim();
+    let repository = repository.strip_prefix("git+").unwrap_or(repository);
+    let repository = match repository.strip_prefix("git://") {
+        Some(rest) => format!("https://{rest}"),
+        None => repository.to_string(),
+    };
+    repository.trim_end_matches('/').trim_end_matches(".git").to_string()
+}
+
+fn string_list(value: &serde_json::Value) -> Vec<String> {
+    value
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|item| item.as_str().map(str::to_string))
+        .collect()
+}
+
+fn parse_crate(response: &serde_json::Value) -> Package {
+    let krate = &response["crate"];
+    let version = match str_field(&krate["max_stable_version"]) {
+        version if version.is_empty() => str_field(&krate["newest_version"]),
+        version => version,
+    };
+    let release = response["versions"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|release| release["num"] == version.as_str());
+    let name = str_field(&krate["name"]);
+    Package {
+        registry: "crates.io",
+        registry_url: format!("https://crates.io/crates/{name}"),
+        name,
+        description: str_field(&krate["description"]).trim().to_string(),
+        license: release.map(|release| str_field(&release["license"])).unwrap_or_default(),
+        repository: repository_link(krate["repository"].as_str().unwrap_or_default()),
+        homepage: str_field(&krate["homepage"]),
+        keywords: string_list(&krate["keywords"]),
+        downloads: krate["downloads"].as_u64(),
+        downloads_period: "all time",
+        published: release.map(|release| str_field(&release["created_at"])).unwrap_or_default(),
+        version,
+    }
+}
+
+/// `response` is the manifest of the latest version, which carries no publish date.
+fn parse_npm(response: &serde_json::Value) -> Package {
+    let version = str_field(&response["version"]);
+    let name = str_field(&response["name"]);
+    // `license` was once an object, and `repository` may be a bare string.
+    let license = match &response["license"] {
+        serde_json::Value::Object(license) => license.get("type").map(str_field).unwrap_or_default(),
+        license => str_field(license),
+    };
+    let repository = match &response["repository"] {
+        serde_json::Value::Object(repository) => repository.get("url").map(str_field).unwrap_or_default(),
+        repository => str_field(repository),
+    };
+    Package {
+        registry: "npm",
+        registry_url: format!("https://www.npmjs.com/package/{name}"),
+        name,
+        description: str_field(&response["description"]),
+        license,
+        repository: repository_link(&repository),
+        homepage: str_field(&response["homepage"]),
+        keywords: string_list(&response["keywords"]),
+        downloads: None,
+        downloads_period: "last month",
+        published: String::new(),
+        version,
+    }
+}
+
+/// The search ranks by relevance, so the top hit is only trusted when it is the package itself.
+fn npm_search_date(search: &serde_json::Value, name: &str) -> String {
+    let package = &search["objects"][0]["package"];
+    if str_field(&package["name"]) == name {
+        str_field(&package["date"])
+    } else {
+        String::new()
+    }
+}
+
+fn parse_pypi(response: &serde_json::Value) -> Package {
+    let info = &response["info"];
+    let version = str_field(&info["version"]);
+    let name = str_field(&info["name"]);
+    let project_urls = &info["project_urls"];
+    let repository = ["Source", "Source Code", "Repository", "Code", "GitHub"]
+        .iter()
+        .map(|key| str_field(&project_urls[key]))
+        .find(|url| !url.is_empty())
+        .unwrap_or_default();
+    // Newer uploads use an SPDX `license_expression`; older ones sometimes paste the whole licence text.
+    let license = match str_field(&info["license_expression"]) {
+        license if license.is_empty() => str_field(&info["license"]).lines().next().unwrap_or_default().to_string(),
+        license => license,
+    };
+    // A single string, comma-separated by convention but space-separated often enough.
+    let keywords = str_field(&info["keywords"]);
+    let keywords: Vec<&str> = if keywords.contains(',') { keywords.split(',').collect() } else { keywords.split_whitespace().collect() };
+    Package {
+        registry: "PyPI",
+        registry_url: format!("https://pypi.org/project/{name}/"),
+        name,
+        description: str_field(&info["summary"]),
+        license,
+        repository: repository_link(&repository),
+        homepage: match str_field(&project_urls["Homepage"]) {
+            homepage if homepage.is_empty() => str_field(&info["home_page"]),
+            homepage => homepage,
+        },
+        keywords: keywords.iter().map(|keyword| keyword.trim()).filter(|keyword| !keyword.is_empty()).map(str::to_string).collect(),
+        downloads: None,
+        downloads_period: "last month",
+        published: str_field(&response["urls"][0]["upload_time_iso_8601"]),
+        version,
+    }
+}
+
+pub async fn fetch(package_url: &PackageUrl) -> Result<Capture> {
+    debug!("fetch: package_url={:?}", package_url);
+    let package = match package_url {
+        PackageUrl::Crate(name) => parse_crate(&http::get_json(&format!("https://crates.io/api/v1/crates/{name}")).await?),
+        PackageUrl::Npm(name) => {
+            // The full packument lists every version and can run past `max_body_size`; `/latest` is one manifest.
+            let mut package = parse_npm(&http::get_json(&format!("https://registry.npmjs.org/{name}/latest")).await?);
+            // The manifest has no date, but a one-result search carries the latest publish date.
+            let search = Url::parse_with_params("https://registry.npmjs.org/-/v1/search", &[("text", name.as_str()), ("size", "1")])?;
+            match http::get_json(search.as_str()).await {
+                Ok(search) => package.published = npm_search_date(&search, name),
+                Err(e) => debug!("fetch: no publish date for npm package {}: {:?}", name, e),
+            }
+            match http::get_json(&format!("https://api.npmjs.org/downloads/point/last-month/{name}")).await {
+                Ok(downloads) => package.downloads = downloads["downloads"].as_u64(),
+                Err(e) => error!("Failed to fetch npm downloads for {}: {:?}", name, e),
+            }
+            package
+        }
+        PackageUrl::PyPi(name) => {
+            let mut package = parse_pypi(&http::get_json(&format!("https://pypi.org/pypi/{name}/json")).await?);
+            match http::get_json(&format!("https://pypistats.org/api/packages/{}/recent", name.to_lowercase())).await {
+                Ok(downloads) => package.downloads = downloads["data"]["last_month"].as_u64(),
+                Err(e) => error!("Failed to fetch PyPI downloads for {}: {:?}", name, e),
+            }
+            package
+        }
+    };
+    Ok(capture(package))
+}
+
+fn capture(package: Package) -> Capture {
+    let mut description = package.description.clone();
+    let mut details = vec![format!("- **Version:** {}", package.version)];
+    if !package.license.is_empty() {
+        details.push(format!("- **License:** {}", package.license));
+    }
+    if let Some(downloads) = package.downloads {
+        details.push(format!("- **Downloads:** {} ({})", downloads, package.downloads_period));
+    }
+    if !package.repository.is_empty() {
+        details.push(format!("- **Repository:** {}", package.repository));
+    }
+    if !package.homepage.is_empty() && package.homepage != package.repository {
+        details.push(format!("- **Homepage:** {}", package.homepage));
+    }
+    details.push(format!("- **Registry:** [{}]({})", package.registry, package.registry_url));
+    description.push_str(&format!("\n\n{}", details.join("\n")));
+
+    let mut extra = vec![
+        ("registry".to_string(), package.registry.to_string()),
+        ("version".to_string(), package.version),
+    ];
+    if !package.license.is_empty() {
+        extra.push(("license".to_string(), package.license));
+    }
+    if !package.repository.is_empty() {
+        extra.push(("repository".to_string(), package.repository));
+    }
+    if let Some(downloads) = package.downloads {
+        extra.push(("downloads".to_string(), downloads.to_string()));
+    }
+
+    Capture {
+        title: package.name,
+        description: description.trim_start().to_string(),
+        tags: package.keywords,
+        published: package.published,
+        extra,
+        note_type: "package".to_string(),
+        ..Capture::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_package_url() {
+        let cases = [
+            ("https://crates.io/crates/serde/1.0.0", Some(PackageUrl::Crate("serde".into()))),
+            ("https://docs.rs/tokio/latest/tokio/", Some(PackageUrl::Crate("tokio".into()))),
+            ("https://docs.rs/crate/regex/latest", Some(PackageUrl::Crate("regex".into()))),
+            ("https://www.npmjs.com/package/@types/node", Some(PackageUrl::Npm("@types/node".into()))),
+            ("https://www.npmjs.com/package/react?activeTab=versions", Some(PackageUrl::Npm("react".into()))),
+            ("https://pypi.org/project/requests/2.31.0/", Some(PackageUrl::PyPi("requests".into()))),
+            ("https://docs.rs/releases", None),
+            ("https://crates.io/categories", None),
+        ];
+        for (url, expected) in cases {
+            assert_eq!(parse_url(url), expected, "url={url}");
+        }
+    }
+
+    #[test]
+    fn test_crate_capture() {
+        let response = json!({"crate": {"name": "serde", "max_stable_version": "1.0.200", "newest_version": "1.0.201-rc",
+            "description": "A serialization framework\n", "repository": "https://github.com/serde-rs/serde", "homepage": "https://serde.rs",
+            "keywords": ["serde", "serialization"], "downloads": 123},
+            "versions": [{"num": "1.0.201-rc", "license": "MIT"}, {"num": "1.0.200", "license": "MIT OR Apache-2.0", "created_at": "2024-05-01T00:00:00Z"}]});
+        let capture = capture(parse_crate(&response));
+        assert_eq!(capture.note_type, "package");
+        assert_eq!(capture.published, "2024-05-01T00:00:00Z");
+        assert_eq!(
+            capture.description,
+            "A serialization framework\n\n- **Version:** 1.0.200\n- **License:** MIT OR Apache-2.0\n- **Downloads:** 123 (all time)\n- **Repository:** https://github.com/serde-rs/serde\n- **Homepage:** https://serde.rs\n- **Registry:** [crates.io](https://crates.io/crates/serde)"
+        );
+        assert_eq!(capture.tags, vec!["serde".to_string(), "serialization".to_string()]);
+        assert_eq!(repository_link("git+https://github.com/facebook/react.git"), "https://github.com/facebook/react");
+        assert_eq!(repository_link("git://github.com/a/b.git"), "https://github.com/a/b");
+    }
+
+    #[test]
+    fn test_npm_latest_manifest() {
+        let response = json!({"name": "react", "version": "18.3.1", "description": "React", "license": "MIT",
+            "repository": {"type": "git", "url": "git+https://github.com/facebook/react.git"}, "keywords": ["react"]});
+        let package = parse_npm(&response);
+        assert_eq!(package.version, "18.3.1");
+        assert_eq!(package.license, "MIT");
+        assert_eq!(package.repository, "https://github.com/facebook/react");
+        assert_eq!(package.keywords, vec!["react".to_string()]);
+        let search = json!({"objects": [{"package": {"name": "react", "date": "2024-04-26T16:42:51.013Z"}}]});
+        assert_eq!(npm_search_date(&search, "react"), "2024-04-26T16:42:51.013Z");
+        assert_eq!(npm_search_date(&search, "preact"), "");
+    }
+}
@@ -0,0 +1,437 @@
+use eyre::{eyre, Result};
+use lazy_static::lazy_static;
+use log::debug;
+use percent_encoding::percent_decode_str;
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::json;
+use url::Url;
+
+use crate::{http, xml, Capture};
+
+lazy_static! {
+    static ref ARXIV_ID_RE: Regex =
+        Regex::new(r"^(\d{4}\.\d{4,5}|[a-z-]+(?:\.[A-Z]{2})?/\d{7})(v\d+)?$").expect("Invalid arXiv id regex");
+    static ref DOI_RE: Regex = Regex::new(r"(10\.\d{4,9}/[^\s?#]+)").expect("Invalid DOI regex");
+    static ref TAG_RE: Regex = Regex::new(r"<[^>]+>").expect("Invalid tag regex");
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct PaperOptions {
+    /// Adds a CSL-JSON block next to the BibTeX entry.
+    #[serde(default)]
+    pub csl_json: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PaperId {
+    Arxiv(String),
+    Doi(String),
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Person {
+    pub given: String,
+    pub family: String,
+}
+
+impl Person {
+    /// arXiv only gives display names, so the last word is taken as the family name.
+    fn from_display_name(name: &str) -> Self {
+        let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+        match name.rsplit_once(' ') {
+            Some((given, family)) => Person {
+                given: given.to_string(),
+                family: family.to_string(),
+            },
+            None => Person {
+                given: String::new(),
+                family: name,
+            },
+        }
+    }
+
+    fn display_name(&self) -> String {
+        format!("{} {}", self.given, self.family).trim().to_string()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Paper {
+    pub title: String,
+    pub authors: Vec<Person>,
+    pub abstract_text: String,
+    pub venue: String,
+    pub year: String,
+    pub published: String,
+    pub pdf_url: String,
+    pub doi: String,
+    pub arxiv_id: String,
+    pub url: String,
+    pub categories: Vec<String>,
+}
+
+pub fn parse_url(url: &str) -> Option<PaperId> {
+    let parsed = Url::parse(url).ok()?;
+    let host = parsed.host_str()?.trim_start_matches("www.").trim_start_matches("export.");
+    let path = percent_decode_str(parsed.path()).decode_utf8_lossy().to_string();
+
+    if host == "arxiv.org" {
+        let id = path
+            .split_once("/abs/")
+            .or_else(|| path.split_once("/pdf/"))
+            .or_else(|| path.split_once("/html/"))?
+            .1
+            .trim_end_matches('/')
+            .trim_end_matches(".pdf");
+        return ARXIV_ID_RE
+            .captures(id)
+            .map(|caps| PaperId::Arxiv(caps[1].to_string()));
+    }
+    if host == "doi.org" || host == "dx.doi.org" || path.contains("/doi/") {
+        return DOI_RE
+            .captures(&path)
+            .map(|caps| PaperId::Doi(caps[1].trim_end_matches('/').to_string()));
+    }
+    None
+}
+
+pub async fn fetch(id: &PaperId) -> Result<Paper> {
+    debug!("fetch: id={:?}", id);
+    match id {
+        PaperId::Arxiv(arxiv_id) => {
+            let url = format!("https://export.arxiv.org/api/query?id_list={arxiv_id}");
+            let response = http::get(&url).await?;
+            if !response.status().is_success() {
+                return Err(eyre!("arXiv lookup for {} failed with status {}", arxiv_id, response.status()));
+            }
+            parse_arxiv(&http::text(response).await?, arxiv_id)
+        }
+        PaperId::Doi(doi) => match fetch_crossref(doi).await {
+            Ok(paper) => Ok(paper),
+            // DataCite, mEDRA and the other registration agencies aren't in Crossref, but every
+            // DOI resolver answers content negotiation for CSL-JSON.
+            Err(e) => {
+                debug!("Crossref has no record for {}, asking doi.org: {:?}", doi, e);
+                fetch_csl_json(doi).await
+            }
+        },
+    }
+}
+
+async fn fetch_crossref(doi: &str) -> Result<Paper> {
+    let url = format!("https://api.crossref.org/works/{doi}");
+    let response = http::get(&url).await?;
+    if !response.status().is_success() {
+        return Err(eyre!("Crossref lookup for {} failed with status {}", doi, response.status()));
+    }
+    Ok(parse_crossref(&http::json(response).await?))
+}
+
+async fn fetch_csl_json(doi: &str) -> Result<Paper> {
+    let url = format!("https://doi.org/{doi}");
+    let response = http::send(http::client().get(&url).header("Accept", "application/vnd.citationstyles.csl+json")).await?;
+    if !response.status().is_success() {
+        return Err(eyre!("DOI lookup for {} failed with status {}", doi, response.status()));
+    }
+    Ok(parse_work(&http::json(response).await?))
+}
+
+fn parse_arxiv(feed: &str, arxiv_id: &str) -> Result<Paper> {
+    let entry = xml::elements(feed, "entry")
+        .into_iter()
+        .next()
+        .filter(|entry| !entry.text("id").contains("api/errors"))
+        .ok_or_else(|| eyre!("arXiv has no entry for {}", arxiv_id))?;
+
+    let published = entry.text("published");
+    let journal_ref = entry.text("arxiv:journal_ref");
+    let pdf_url = entry
+        .elements("link")
+        .iter()
+        .find(|link| link.attr("title").as_deref() == Some("pdf"))
+        .and_then(|link| link.attr("href"))
+        .unwrap_or_else(|| format!("https://arxiv.org/pdf/{arxiv_id}"));
+    let mut categories: Vec<String> = entry.elements("category").iter().filter_map(|category| category.attr("term")).collect();
+    categories.dedup();
+    Ok(Paper {
+        title: entry.text("title").split_whitespace().collect::<Vec<_>>().join(" "),
+        authors: entry
+            .elements("author")
+            .iter()
+            .map(|author| Person::from_display_name(&author.text("name")))
+            .collect(),
+        abstract_text: entry.text("summary").split_whitespace().collect::<Vec<_>>().join(" "),
+        venue: if journal_ref.is_empty() { "arXiv".to_string() } else { journal_ref },
+        year: published.chars().take(4).collect(),
+        published,
+        pdf_url,
+        doi: entry.text("arxiv:doi"),
+        arxiv_id: arxiv_id.to_string(),
+        url: format!("https://arxiv.org/abs/{arxiv_id}"),
+        categories,
+    })
+}
+
+fn str_field(value: &serde_json::Value) -> String {
+    value.as_str().unwrap_or_default().to_string()
+}
+
+/// Crossref wraps titles in a list; CSL-JSON from doi.org usually doesn't.
+fn first_str(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Array(values) => values.first().map(str_field).unwrap_or_default(),
+        value => str_field(value),
+    }
+}
+
+fn names(values: &serde_json::Value) -> Vec<String> {
+    values
+        .as_array()
+        .map(|values| values.iter().filter_map(|value| value.as_str()).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+fn parse_crossref(response: &serde_json::Value) -> Paper {
+    parse_work(&response["message"])
+}
+
+/// Reads a Crossref work or a CSL-JSON item; the former is close to a superset of the latter.
+fn parse_work(work: &serde_json::Value) -> Paper {
+    let date_parts: Vec<u64> = ["published", "issued", "created"]
+        .iter()
+        .filter_map(|key| work[key]["date-parts"][0].as_array())
+        .map(|parts| parts.iter().filter_map(|part| part.as_u64()).collect::<Vec<_>>())
+        .find(|parts| !parts.is_empty())
+        .unwrap_or_default();
+    let published = match date_parts.as_slice() {
+        [year, month, day, ..] => format!("{year:04}-{month:02}-{day:02}"),
+        [year, month] => format!("{year:04}-{month:02}-01"),
+        [year] => year.to_string(),
+        [] => String::new(),
+    };
+    let pdf_url = work["link"]
+        .as_array()
+        .and_then(|links| links.iter().find(|link| link["content-type"] == "application/pdf"))
+        .map(|link| str_field(&link["URL"]))
+        .unwrap_or_default();
+    let abstract_text = TAG_RE.replace_all(&str_field(&work["abstract"]), " ").to_string();
+
+    Paper {
+        title: first_str(&work["title"]),
+        authors: work["author"]
+            .as_array()
+            .map(|authors| {
+                authors
+                    .iter()
+                    .map(|author| match str_field(&author["family"]) {
+                        // Organisations only have a name (`literal` in CSL), which must not be split.
+                        family if family.is_empty() => Person {
+                            given: String::new(),
+                            family: ["name", "literal"].iter().map(|key| str_field(&author[key])).find(|name| !name.is_empty()).unwrap_or_default(),
+                        },
+                        family => Person {
+                            given: str_field(&author["given"]),
+                            family,
+                        },
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+        abstract_text: xml::unescape(&abstract_text).split_whitespace().collect::<Vec<_>>().join(" "),
+        venue: first_str(&work["container-title"]),
+        year: date_parts.first().map(u64::to_string).unwrap_or_default(),
+        published,
+        pdf_url,
+        doi: str_field(&work["DOI"]),
+        arxiv_id: String::new(),
+        url: str_field(&work["URL"]),
+        categories: names(&work["subject"]),
+    }
+}
+
+/// `vaswani2017attention`, the key style Google Scholar and most reference managers use.
+fn citation_key(paper: &Paper) -> String {
+    let family = paper.authors.first().map(|author| author.family.as_str()).unwrap_or("anonymous");
+    let word = paper
+        .title
+        .split(|c: char| !c.is_alphanumeric())
+        .find(|word| word.len() > 3 && !["with", "from", "that", "this", "what", "when", "where"].contains(&word.to_lowercase().as_str()))
+        .unwrap_or("paper");
+    format!("{}{}{}", family, paper.year, word)
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Escapes the characters LaTeX treats specially in running text. `doi` and `url` are verbatim fields and stay as they are.
+fn latex_escape(text: &str) -> String {
+    text.chars().fold(String::with_capacity(text.len()), |mut escaped, c| {
+        if matches!(c, '&' | '%' | '_' | '#') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+        escaped
+    })
+}
+
+pub fn bibtex(paper: &Paper) -> String {
+    let is_preprint = !paper.arxiv_id.is_empty() && paper.venue == "arXiv";
+    let mut fields = vec![
+        ("title", format!("{{{}}}", latex_escape(&paper.title))),
+        (
+            "author",
+            paper
+                .authors
+                .iter()
+                .map(|author| {
+                    if author.given.is_empty() {
+                        format!("{{{}}}", latex_escape(&author.family))
+                    } else {
+                        format!("{}, {}", latex_escape(&author.family), latex_escape(&author.given))
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" and "),
+        ),
+    ];
+    if !is_preprint && !paper.venue.is_empty() {
+        fields.push(("journal", latex_escape(&paper.venue)));
+    }
+    fields.push(("year", paper.year.clone()));
+    fields.push(("doi", paper.doi.clone()));
+    if !paper.arxiv_id.is_empty() {
+        fields.push(("eprint", paper.arxiv_id.clone()));
+        fields.push(("archivePrefix", "arXiv".to_string()));
+    }
+    fields.push(("url", paper.url.clone()));
+
+    let body = fields
+        .into_iter()
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(key, value)| format!("  {key} = {{{value}}}"))
+        .collect::<Vec<_>>()
+        .join(",\n");
+    let kind = if is_preprint { "misc" } else { "article" };
+    format!("@{}{{{},\n{}\n}}", kind, citation_key(paper), body)
+}
+
+pub fn csl_json(paper: &Paper) -> Result<String> {
+    let mut item = json!({
+        "id": citation_key(paper),
+        "type": if paper.arxiv_id.is_empty() { "article-journal" } else { "article" },
+        "title": paper.title,
+        "author": paper.authors.iter().map(|author| json!({"given": author.given, "family": author.family})).collect::<Vec<_>>(),
+        "URL": paper.url,
+    });
+    if let Ok(year) = paper.year.parse::<u32>() {
+        item["issued"] = json!({"date-parts": [[year]]});
+    }
+    for (key, value) in [("container-title", &paper.venue), ("DOI", &paper.doi), ("abstract", &paper.abstract_text)] {
+        if !value.is_empty() {
+            item[key] = json!(value);
+        }
+    }
+    Ok(serde_json::to_string_pretty(&item)?)
+}
+
+pub fn capture(paper: &Paper, options: &PaperOptions) -> Result<Capture> {
+    let mut description = format!("{}\n\n## Citation\n```bibtex\n{}\n```", paper.abstract_text, bibtex(paper));
+    if options.csl_json {
+        description.push_str(&format!("\n\n```json\n{}\n```", csl_json(paper)?));
+    }
+
+    let mut extra = Vec::new();
+    for (key, value) in [
+        ("venue", &paper.venue),
+        ("year", &paper.year),
+        ("doi", &paper.doi),
+        ("arxiv", &paper.arxiv_id),
+        ("pdf", &paper.pdf_url),
+        ("citekey", &citation_key(paper)),
+    ] {
+        if !value.is_empty() {
+            extra.push((key.to_string(), value.clone()));
+        }
+    }
+
+    Ok(Capture {
+        title: paper.title.clone(),
+        description,
+        embed_code: if paper.pdf_url.is_empty() { String::new() } else { format!("[PDF]({})", paper.pdf_url) },
+        authors: paper.authors.iter().map(Person::display_name).collect(),
+        tags: paper.categories.clone(),
+        published: paper.published.clone(),
+        extra,
+        ..Capture::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_paper_url() {
+        let cases = [
+            ("https://arxiv.org/abs/1706.03762v7", Some(PaperId::Arxiv("1706.03762".into()))),
+            ("https://arxiv.org/pdf/1706.03762.pdf", Some(PaperId::Arxiv("1706.03762".into()))),
+            ("https://arxiv.org/abs/hep-th/9901001", Some(PaperId::Arxiv("hep-th/9901001".into()))),
+            ("https://doi.org/10.1145/3290605.3300233", Some(PaperId::Doi("10.1145/3290605.3300233".into()))),
+            ("https://dl.acm.org/doi/10.1145/3290605.3300233", Some(PaperId::Doi("10.1145/3290605.3300233".into()))),
+            ("https://doi.org/10.1002/(SICI)1097-4571(199806)49:8%3C693::AID-ASI4%3E3.0.CO;2-0",
+                Some(PaperId::Doi("10.1002/(SICI)1097-4571(199806)49:8<693::AID-ASI4>3.0.CO;2-0".into()))),
+            ("https://arxiv.org/list/cs.CL/recent", None),
+            ("https://example.com/paper", None),
+        ];
+        for (url, expected) in cases {
+            assert_eq!(parse_url(url), expected, "url={url}");
+        }
+    }
+
+    #[test]
+    fn test_arxiv_bibtex() -> Result<()> {
+        let feed = r#"<feed><title>ArXiv Query</title><entry><id>http://arxiv.org/abs/1706.03762v7</id>
+            <published>2017-06-12T17:57:34Z</published><title>Attention Is All
+              You Need</title><summary>  The dominant sequence transduction models...</summary>
+            <author><name>Ashish Vaswani</name></author><author><name>Noam Shazeer</name></author>
+            <link title="pdf" href="http://arxiv.org/pdf/1706.03762v7" rel="related" type="application/pdf"/>
+            <category term="cs.CL" scheme="http://arxiv.org/schemas/atom"/><category term="cs.LG" scheme="http://arxiv.org/schemas/atom"/>
+            </entry></feed>"#;
+        let paper = parse_arxiv(feed, "1706.03762")?;
+        assert_eq!(paper.title, "Attention Is All You Need");
+        assert_eq!(paper.pdf_url, "http://arxiv.org/pdf/1706.03762v7");
+        assert_eq!(paper.categories, vec!["cs.CL".to_string(), "cs.LG".to_string()]);
+        assert_eq!(
+            bibtex(&paper),
+            "@misc{vaswani2017attention,\n  title = {{Attention Is All You Need}},\n  author = {Vaswani, Ashish and Shazeer, Noam},\n  year = {2017},\n  eprint = {1706.03762},\n  archivePrefix = {arXiv},\n  url = {https://arxiv.org/abs/1706.03762}\n}"
+        );
+        let csl: serde_json::Value = serde_json::from_str(&csl_json(&paper)?)?;
+        assert_eq!(csl["author"][0]["family"], "Vaswani");
+        assert!(parse_arxiv("<feed><entry><id>http://arxiv.org/api/errors#incorrect_id_format</id></entry></feed>", "x").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_crossref() {
+        let response = json!({"message": {"DOI": "10.1000/xyz", "URL": "https://doi.org/10.1000/xyz",
+            "title": ["A Study"], "container-title": ["Journal of Things"],
+            "author": [{"given": "Ada", "family": "Lovelace"}, {"name": "The Consortium"}],
+            "abstract": "<jats:p>We &amp; they.</jats:p>", "issued": {"date-parts": [[1843, 9]]},
+            "link": [{"URL": "https://example.com/a.pdf", "content-type": "application/pdf"}]}});
+        let paper = parse_crossref(&response);
+        assert_eq!(paper.published, "1843-09-01");
+        assert_eq!(paper.abstract_text, "We & they.");
+        assert_eq!(paper.authors[1].display_name(), "The Consortium");
+        assert!(bibtex(&paper).starts_with("@article{lovelace1843study,\n  title = {{A Study}},\n  author = {Lovelace, Ada and {The Consortium}},\n  journal = {Journal of Things},"));
+
+        // The same work as CSL-JSON from doi.org content negotiation.
+        let csl = json!({"DOI": "10.5281/zenodo.1", "URL": "https://doi.org/10.5281/zenodo.1", "title": "R&D at 100% for C#",
+            "container-title": "Zenodo", "author": [{"literal": "Data_Lab"}], "issued": {"date-parts": [[2020, 1, 2]]}});
+        let paper = parse_work(&csl);
+        assert_eq!(paper.title, "R&D at 100% for C#");
+        assert_eq!(paper.published, "2020-01-02");
+        assert!(bibtex(&paper).contains("  title = {{R\\&D at 100\\% for C\\#}},\n  author = {{Data\\_Lab}},\n  journal = {Zenodo},\n  year = {2020},\n  doi = {10.5281/zenodo.1},"));
+    }
+}
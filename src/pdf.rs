@@ -0,0 +1,148 @@
+use eyre::{eyre, Result};
+use lazy_static::lazy_static;
+use log::debug;
+use lopdf::{decode_text_string, Document, Object};
+use regex::Regex;
+
+lazy_static! {
+    static ref PDF_DATE_RE: Regex =
+        Regex::new(r"^(?:D:)?(\d{4})(\d{2})?(\d{2})?(?:(\d{2})(\d{2})?(\d{2})?)?(Z|([+-])(\d{2})'?(\d{2})?'?)?").expect("Invalid PDF date regex");
+}
+
+/// Text extraction is slow on large documents; the note only needs the opening pages.
+const MAX_TEXT_PAGES: u32 = 20;
+
+#[derive(Debug, Default)]
+pub struct PdfInfo {
+    pub title: String,
+    pub author: String,
+    pub pages: usize,
+    pub text: String,
+    /// `CreationDate` as ISO 8601, empty when the document doesn't record one.
+    pub created: String,
+}
+
+fn info_field(document: &Document, key: &[u8]) -> String {
+    let Ok(info) = document.trailer.get(b"Info") else {
+        return String::new();
+    };
+    document
+        .dereference(info)
+        .and_then(|(_, info)| info.as_dict())
+        .and_then(|info| info.get(key))
+        .and_then(|value| match value {
+            Object::Reference(id) => document.get_object(*id),
+            value => Ok(value),
+        })
+        .and_then(decode_text_string)
+        .map(|value| value.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// PDF dates look like `D:20240131093000+01'00'`; everything after the year is optional.
+fn iso_date(raw: &str) -> String {
+    let Some(caps) = PDF_DATE_RE.captures(raw.trim()) else {
+        return String::new();
+    };
+    let part = |i: usize, default: &str| caps.get(i).map_or(default.to_string(), |m| m.as_str().to_string());
+    let date = format!("{}-{}-{}", &caps[1], part(2, "01"), part(3, "01"));
+    if caps.get(4).is_none() {
+        return date;
+    }
+    let offset = match (caps.get(7).map(|m| m.as_str()), caps.get(8)) {
+        (Some("Z"), _) => "Z".to_string(),
+        (Some(_), Some(sign)) => format!("{}{}:{}", sign.as_str(), &caps[9], part(10, "00")),
+        _ => String::new(),
+    };
+    format!("{}T{}:{}:{}{}", date, &caps[4], part(5, "00"), part(6, "00"), offset)
+}
+
+pub fn extract(bytes: &[u8]) -> Result<PdfInfo> {
+    let document = Document::load_mem(bytes).map_err(|e| eyre!("Failed to parse PDF: {}", e))?;
+    let pages: Vec<u32> = document.get_pages().keys().copied().collect();
+    let text_pages: Vec<u32> = pages.iter().copied().filter(|page| *page <= MAX_TEXT_PAGES).collect();
+    let text = document.extract_text(&text_pages).unwrap_or_else(|e| {
+        debug!("Failed to extract PDF text: {}", e);
+        String::new()
+    });
+
+    Ok(PdfInfo {
+        title: info_field(&document, b"Title"),
+        author: info_field(&document, b"Author"),
+        pages: pages.len(),
+        text: text.trim().to_string(),
+        created: iso_date(&info_field(&document, b"CreationDate")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::content::{Content, Operation};
+    use lopdf::{dictionary, Stream};
+
+    fn sample_pdf() -> Vec<u8> {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Courier",
+        });
+        let resources_id = doc.add_object(dictionary! {
+            "Font" => dictionary! { "F1" => font_id },
+        });
+        let content = Content {
+            operations: vec![
+                Operation::new("BT", vec![]),
+                Operation::new("Tf", vec!["F1".into(), 24.into()]),
+                Operation::new("Td", vec![100.into(), 600.into()]),
+                Operation::new("Tj", vec![Object::string_literal("Hello PDF")]),
+                Operation::new("ET", vec![]),
+            ],
+        };
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().expect("encode content")));
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+        });
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![page_id.into()],
+                "Count" => 1,
+                "Resources" => resources_id,
+                "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        let info_id = doc.add_object(dictionary! {
+            "Title" => Object::string_literal("Sample Paper"),
+            "Author" => Object::string_literal("Jane Doe"),
+            "CreationDate" => Object::string_literal("D:20240131093000+01'00'"),
+        });
+        doc.trailer.set("Root", catalog_id);
+        doc.trailer.set("Info", info_id);
+        let mut bytes = Vec::new();
+        doc.save_to(&mut bytes).expect("save pdf");
+        bytes
+    }
+
+    #[test]
+    fn test_extract_pdf_metadata() -> Result<()> {
+        let info = extract(&sample_pdf())?;
+        assert_eq!(info.title, "Sample Paper");
+        assert_eq!(info.author, "Jane Doe");
+        assert_eq!(info.pages, 1);
+        assert!(info.text.contains("Hello PDF"), "text was {:?}", info.text);
+        assert_eq!(info.created, "2024-01-31T09:30:00+01:00");
+        assert_eq!(iso_date("D:2023"), "2023-01-01");
+        assert_eq!(iso_date("D:20230405120000Z"), "2023-04-05T12:00:00Z");
+        Ok(())
+    }
+}
@@ -0,0 +1,270 @@
+use eyre::{eyre, Result};
+use log::debug;
+use scraper::{Html, Selector};
+use url::Url;
+
+use crate::{content, http, jsonld, opengraph, video, xml, Capture};
+
+#[derive(Debug, PartialEq)]
+pub struct AppleEpisode {
+    pub show_id: String,
+    pub episode_id: u64,
+}
+
+/// How to recognise the bookmarked episode among a feed's items.
+#[derive(Debug, Default)]
+pub struct EpisodeMatch {
+    pub guid: String,
+    pub link: String,
+    pub title: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Episode {
+    pub show: String,
+    pub title: String,
+    pub author: String,
+    pub published: String,
+    pub duration: String,
+    pub show_notes: String,
+    pub audio_url: String,
+    pub image: String,
+    pub feed_url: String,
+}
+
+pub fn parse_url(url: &str) -> Option<AppleEpisode> {
+    let parsed = Url::parse(url).ok()?;
+    if parsed.host_str()? != "podcasts.apple.com" {
+        return None;
+    }
+    let show_id = parsed
+        .path_segments()?
+        .rfind(|segment| segment.starts_with("id"))?
+        .trim_start_matches("id")
+        .to_string();
+    // Without `?i=` this is the show page rather than an episode.
+    let episode_id = parsed.query_pairs().find(|(key, _)| key == "i")?.1.parse().ok()?;
+    Some(AppleEpisode { show_id, episode_id })
+}
+
+/// Whether a page is about a single episode: its JSON-LD `entities` say so, or it carries a player.
+pub fn looks_like_episode(document: &Html, entities: &[serde_json::Value]) -> bool {
+    let selector = Selector::parse("audio, meta[property='og:audio']").expect("Invalid audio selector");
+    jsonld::find_entity(entities, "PodcastEpisode").is_some() || document.select(&selector).next().is_some()
+}
+
+/// The show's feed, from `<link rel="alternate" type="application/rss+xml">`; podcasts don't ship Atom.
+pub fn feed_url(document: &Html, base: &Url) -> Option<String> {
+    let selector = Selector::parse("link[rel~='alternate'][type='application/rss+xml'][href]").expect("Invalid feed selector");
+    document
+        .select(&selector)
+        .filter_map(|link| base.join(link.value().attr("href")?.trim()).ok())
+        .map(String::from)
+        .next()
+}
+
+/// What to look for in the feed when the bookmark is the episode's own web page.
+pub fn episode_match(document: &Html, url: &str) -> EpisodeMatch {
+    let selector = Selector::parse("title").expect("Invalid title selector");
+    let html_title = document.select(&selector).next().map(|title| title.text().collect::<String>()).unwrap_or_default();
+    EpisodeMatch {
+        guid: String::new(),
+        link: url.to_string(),
+        title: opengraph::extract(document).best_title(html_title.trim()),
+    }
+}
+
+/// Lowercase letters and digits only, so "Ep. 12: Title" and "Ep 12 – Title" compare equal.
+fn normalize(text: &str) -> String {
+    text.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+/// `itunes:duration` is either seconds or `[H:]MM:SS`; notes get the latter.
+fn duration(raw: &str) -> String {
+    raw.trim().parse().map_or_else(|_| raw.trim().to_string(), video::format_duration)
+}
+
+fn parse_feed(feed: &str, feed_url: &str, wanted: &EpisodeMatch) -> Option<Episode> {
+    // Channel-level elements come before the first item; don't let an item's <title> stand in for the show's.
+    let channel = feed.split("<item").next().unwrap_or(feed);
+    let trimmed = |link: &str| link.trim().trim_end_matches('/').to_string();
+    // Page titles usually add the show name around the episode title; take it out before comparing.
+    let show = normalize(&xml::text(channel, "title"));
+    let wanted_title = match normalize(&wanted.title) {
+        title if !show.is_empty() && title != show => title.replace(&show, ""),
+        title => title,
+    };
+
+    // A blog's feed can match the page too; only an item with an audio enclosure is an episode.
+    let audio = |item: &xml::Element| {
+        item.elements("enclosure")
+            .into_iter()
+            .find(|enclosure| enclosure.attr("type").is_some_and(|kind| kind.starts_with("audio/")))
+            .and_then(|enclosure| enclosure.attr("url"))
+    };
+    let items: Vec<_> = xml::elements(feed, "item").into_iter().filter(|item| audio(item).is_some()).collect();
+    // What's left must be mostly the item's title, so "Bonus" or "Ep 1" can't claim "Ep 12: Bonus Round".
+    let matches_title = |item: &xml::Element| {
+        let title = normalize(&item.text("title"));
+        !title.is_empty() && wanted_title.contains(&title) && title.len() * 3 >= wanted_title.len() * 2
+    };
+    let item = items
+        .iter()
+        .find(|item| !wanted.guid.is_empty() && item.text("guid") == wanted.guid)
+        .or_else(|| items.iter().find(|item| !wanted.link.is_empty() && trimmed(&item.text("link")) == trimmed(&wanted.link)))
+        .or_else(|| items.iter().filter(|item| matches_title(item)).max_by_key(|item| normalize(&item.text("title")).len()))
+        .copied()?;
+
+    let notes = [item.text("content:encoded"), item.text("description"), item.text("itunes:summary")]
+        .into_iter()
+        .find(|notes| !notes.is_empty())
+        .unwrap_or_default();
+    let image = item
+        .elements("itunes:image")
+        .into_iter()
+        .chain(xml::elements(channel, "itunes:image"))
+        .find_map(|image| image.attr("href"))
+        .unwrap_or_default();
+    let author = match item.text("itunes:author") {
+        author if author.is_empty() => xml::text(channel, "itunes:author"),
+        author => author,
+    };
+
+    Some(Episode {
+        show: xml::text(channel, "title"),
+        title: item.text("title"),
+        author,
+        published: item.text("pubDate"),
+        duration: duration(&item.text("itunes:duration")),
+        show_notes: content::fragment_to_markdown(&notes),
+        audio_url: audio(&item).unwrap_or_default(),
+        image,
+        feed_url: feed_url.to_string(),
+    })
+}
+
+pub async fn fetch_episode(feed_url: &str, wanted: &EpisodeMatch) -> Result<Episode> {
+    debug!("fetch_episode: feed_url={} wanted={:?}", feed_url, wanted);
+    let response = http::get(feed_url).await?;
+    if !response.status().is_success() {
+        return Err(eyre!("Request to {} failed with status {}", feed_url, response.status()));
+    }
+    let feed = http::text(response).await?;
+    parse_feed(&feed, feed_url, wanted).ok_or_else(|| eyre!("Episode not found in feed {}", feed_url))
+}
+
+/// Apple Podcasts pages carry no feed link, but the iTunes lookup API knows the feed and the episode's GUID.
+pub async fn fetch_apple(apple: &AppleEpisode) -> Result<Episode> {
+    debug!("fetch_apple: apple={:?}", apple);
+    let lookup_url = format!("https://itunes.apple.com/lookup?id={}&entity=podcastEpisode&limit=200", apple.show_id);
+    let lookup: serde_json::Value = http::get_json(&lookup_url).await?;
+    let results = lookup["results"].as_array().cloned().unwrap_or_default();
+    let feed_url = results
+        .iter()
+        .find_map(|result| result["feedUrl"].as_str())
+        .ok_or_else(|| eyre!("No feed for Apple Podcasts show {}", apple.show_id))?;
+    let episode = results
+        .iter()
+        .find(|result| result["trackId"].as_u64() == Some(apple.episode_id))
+        .ok_or_else(|| eyre!("Episode {} not among the show's recent episodes", apple.episode_id))?;
+    let wanted = EpisodeMatch {
+        guid: episode["episodeGuid"].as_str().unwrap_or_default().to_string(),
+        link: String::new(),
+        title: episode["trackName"].as_str().unwrap_or_default().to_string(),
+    };
+    fetch_episode(feed_url, &wanted).await
+}
+
+pub fn capture(episode: &Episode) -> Capture {
+    let mut details = vec![format!("- **Show:** {}", episode.show)];
+    if !episode.duration.is_empty() {
+        details.push(format!("- **Duration:** {}", episode.duration));
+    }
+    if !episode.audio_url.is_empty() {
+        details.push(format!("- **Audio:** {}", episode.audio_url));
+    }
+    let mut description = details.join("\n");
+    if !episode.show_notes.is_empty() {
+        description.push_str(&format!("\n\n## Show notes\n{}", episode.show_notes));
+    }
+
+    let mut extra = vec![("show".to_string(), episode.show.clone())];
+    for (key, value) in [
+        ("duration", &episode.duration),
+        ("audio_url", &episode.audio_url),
+        ("feed_url", &episode.feed_url),
+    ] {
+        if !value.is_empty() {
+            extra.push((key.to_string(), value.clone()));
+        }
+    }
+
+    Capture {
+        title: episode.title.clone(),
+        description,
+        embed_code: if episode.audio_url.is_empty() {
+            String::new()
+        } else {
+            format!(r#"<audio controls preload="none" src="{}"></audio>"#, episode.audio_url)
+        },
+        authors: vec![if episode.author.is_empty() { episode.show.clone() } else { episode.author.clone() }],
+        published: episode.published.clone(),
+        extra,
+        note_type: "podcast".to_string(),
+        ..Capture::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FEED: &str = r#"<rss><channel><title>Rust Talk</title><itunes:author>Ferris</itunes:author>
+        <itunes:image href="https://example.com/show.jpg"/>
+        <item><title>Ep. 2: Async</title><link>https://example.com/2</link><guid>ep-2</guid>
+            <enclosure url="https://cdn.example.com/2.mp3" type="audio/mpeg" length="1"/></item>
+        <item><title>Ep. 1: Ownership &amp; Borrowing</title><link>https://example.com/1/</link><guid isPermaLink="false">ep-1</guid>
+            <pubDate>Mon, 01 Jan 2024 10:00:00 GMT</pubDate><itunes:duration>3725</itunes:duration>
+            <description><![CDATA[<p>We talk <b>borrowing</b>.</p>]]></description>
+            <enclosure url="https://cdn.example.com/1.mp3" type="audio/mpeg" length="1"/></item>
+    </channel></rss>"#;
+
+    #[test]
+    fn test_parse_apple_podcasts_url() {
+        assert_eq!(
+            parse_url("https://podcasts.apple.com/us/podcast/rust-talk/id1234567890?i=1000600000001"),
+            Some(AppleEpisode {
+                show_id: "1234567890".to_string(),
+                episode_id: 1000600000001,
+            })
+        );
+        assert_eq!(parse_url("https://podcasts.apple.com/us/podcast/rust-talk/id1234567890"), None);
+    }
+
+    #[test]
+    fn test_podcast_episode_from_feed() {
+        for wanted in [
+            EpisodeMatch { link: "https://example.com/1".into(), ..EpisodeMatch::default() },
+            EpisodeMatch { title: "Ep 1 – Ownership & Borrowing | Rust Talk".into(), ..EpisodeMatch::default() },
+            EpisodeMatch { guid: "ep-1".into(), ..EpisodeMatch::default() },
+        ] {
+            let episode = parse_feed(FEED, "https://example.com/feed.xml", &wanted).expect("expected an episode");
+            assert_eq!(episode.title, "Ep. 1: Ownership & Borrowing", "{wanted:?}");
+        }
+        let episode = parse_feed(FEED, "https://example.com/feed.xml", &EpisodeMatch { guid: "ep-1".into(), ..EpisodeMatch::default() })
+            .expect("expected an episode");
+        let capture = capture(&episode);
+        assert_eq!(episode.image, "https://example.com/show.jpg");
+        assert_eq!(capture.authors, vec!["Ferris".to_string()]);
+        assert_eq!(
+            capture.description,
+            "- **Show:** Rust Talk\n- **Duration:** 1:02:05\n- **Audio:** https://cdn.example.com/1.mp3\n\n## Show notes\nWe talk **borrowing**."
+        );
+        assert_eq!(capture.embed_code, r#"<audio controls preload="none" src="https://cdn.example.com/1.mp3"></audio>"#);
+        assert!(parse_feed(FEED, "", &EpisodeMatch { guid: "ep-9".into(), ..EpisodeMatch::default() }).is_none());
+        let longer = EpisodeMatch { title: "Ep. 2: Async and Await, Part Two of Many | Rust Talk".into(), ..EpisodeMatch::default() };
+        assert!(parse_feed(FEED, "", &longer).is_none());
+        let blog = r#"<rss><channel><title>Blog</title><item><title>Post</title><link>https://example.com/post</link></item></channel></rss>"#;
+        assert!(parse_feed(blog, "", &EpisodeMatch { link: "https://example.com/post".into(), ..EpisodeMatch::default() }).is_none());
+    }
+}
@@ -0,0 +1,207 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::Value;
+
+use crate::{jsonld, xml, Capture};
+
+lazy_static! {
+    static ref DURATION_RE: Regex =
+        Regex::new(r"^P(?:(\d+)D)?(?:T(?:(\d+)H)?(?:(\d+)M)?(?:\d+(?:\.\d+)?S)?)?$").expect("Invalid duration regex");
+    static ref TAG_RE: Regex = Regex::new(r"<[^>]+>").expect("Invalid tag regex");
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Section {
+    pub name: String,
+    pub steps: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct Recipe {
+    pub name: String,
+    pub description: String,
+    pub authors: Vec<String>,
+    pub published: String,
+    pub image: String,
+    pub recipe_yield: String,
+    pub prep_time: String,
+    pub cook_time: String,
+    pub total_time: String,
+    pub ingredients: Vec<String>,
+    pub instructions: Vec<Section>,
+    pub keywords: Vec<String>,
+}
+
+/// Sites entity-encode and sometimes tag-wrap their JSON-LD strings.
+fn clean(value: &str) -> String {
+    let text = xml::unescape(&TAG_RE.replace_all(value, ""));
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// `PT1H30M` -> `1 h 30 min`; anything unparseable is passed through.
+fn duration(iso: &str) -> String {
+    let Some(caps) = DURATION_RE.captures(iso.trim()) else {
+        return iso.trim().to_string();
+    };
+    let parts: Vec<String> = [(1, "d"), (2, "h"), (3, "min")]
+        .iter()
+        .filter_map(|(group, unit)| caps.get(*group).map(|m| m.as_str().trim_start_matches('0')).filter(|n| !n.is_empty()).map(|n| format!("{n} {unit}")))
+        .collect();
+    parts.join(" ")
+}
+
+fn steps(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(text) => text.lines().map(clean).filter(|step| !step.is_empty()).collect(),
+        Value::Array(items) => items.iter().flat_map(steps).collect(),
+        Value::Object(step) => step
+            .get("text")
+            .or_else(|| step.get("name"))
+            .map(|text| vec![clean(&jsonld::text(text))])
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+/// `recipeInstructions` is a string, a list of strings or `HowToStep`s, or a list of `HowToSection`s.
+fn sections(value: &Value) -> Vec<Section> {
+    let items = match value {
+        Value::Array(items) => items.as_slice(),
+        other => std::slice::from_ref(other),
+    };
+    let mut sections: Vec<Section> = Vec::new();
+    for item in items {
+        if item["@type"] == "HowToSection" {
+            sections.push(Section {
+                name: clean(&jsonld::text(&item["name"])),
+                steps: steps(&item["itemListElement"]),
+            });
+            continue;
+        }
+        match sections.last_mut() {
+            Some(section) if section.name.is_empty() => section.steps.extend(steps(item)),
+            _ => sections.push(Section {
+                name: String::new(),
+                steps: steps(item),
+            }),
+        }
+    }
+    sections.retain(|section| !section.steps.is_empty());
+    sections
+}
+
+/// The page's schema.org `Recipe`, if its JSON-LD `entities` include one.
+pub fn from_entities(entities: &[Value]) -> Option<Recipe> {
+    let entity = jsonld::find_entity(entities, "Recipe")?;
+    // Yields are often given twice, e.g. ["4", "4 servings"]; the wordier one reads better.
+    let recipe_yield = match &entity["recipeYield"] {
+        Value::Array(items) => items.iter().map(jsonld::text).max_by_key(|text| text.len()).unwrap_or_default(),
+        other => jsonld::text(other),
+    };
+    Some(Recipe {
+        name: clean(&jsonld::text(&entity["name"])),
+        description: clean(&jsonld::text(&entity["description"])),
+        authors: jsonld::names(&entity["author"]),
+        published: jsonld::text(&entity["datePublished"]),
+        image: jsonld::image_url(&entity["image"]),
+        recipe_yield,
+        prep_time: duration(&jsonld::text(&entity["prepTime"])),
+        cook_time: duration(&jsonld::text(&entity["cookTime"])),
+        total_time: duration(&jsonld::text(&entity["totalTime"])),
+        ingredients: entity["recipeIngredient"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|ingredient| clean(&jsonld::text(ingredient)))
+            .filter(|ingredient| !ingredient.is_empty())
+            .collect(),
+        instructions: sections(&entity["recipeInstructions"]),
+        keywords: ["keywords", "recipeCuisine", "recipeCategory"]
+            .iter()
+            .flat_map(|key| jsonld::keywords(&entity[key]))
+            .collect(),
+    })
+}
+
+pub fn capture(recipe: &Recipe) -> Capture {
+    let mut description = String::new();
+    if !recipe.description.is_empty() {
+        description.push_str(&format!("{}\n\n", recipe.description));
+    }
+
+    let mut extra = Vec::new();
+    let mut details = Vec::new();
+    for (key, label, value) in [
+        ("yield", "Yield", &recipe.recipe_yield),
+        ("prep_time", "Prep time", &recipe.prep_time),
+        ("cook_time", "Cook time", &recipe.cook_time),
+        ("total_time", "Total time", &recipe.total_time),
+    ] {
+        if !value.is_empty() {
+            details.push(format!("- **{label}:** {value}"));
+            extra.push((key.to_string(), value.clone()));
+        }
+    }
+    if !details.is_empty() {
+        description.push_str(&format!("{}\n\n", details.join("\n")));
+    }
+
+    let ingredients = recipe.ingredients.iter().map(|ingredient| format!("- [ ] {ingredient}")).collect::<Vec<_>>();
+    description.push_str(&format!("## Ingredients\n{}\n\n## Instructions\n", ingredients.join("\n")));
+    for section in &recipe.instructions {
+        if !section.name.is_empty() {
+            description.push_str(&format!("### {}\n", section.name));
+        }
+        let steps = section.steps.iter().enumerate().map(|(i, step)| format!("{}. {}", i + 1, step)).collect::<Vec<_>>();
+        description.push_str(&format!("{}\n\n", steps.join("\n")));
+    }
+
+    Capture {
+        title: recipe.name.clone(),
+        description: description.trim_end().to_string(),
+        authors: recipe.authors.clone(),
+        tags: recipe.keywords.clone(),
+        published: recipe.published.clone(),
+        extra,
+        note_type: "recipe".to_string(),
+        ..Capture::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scraper::Html;
+
+    #[test]
+    fn test_recipe_capture() {
+        let html = r#"<script type="application/ld+json">{"@graph": [{"@type": "WebPage"}, {"@type": "Recipe",
+            "name": "Pancakes &amp; Syrup", "description": "Fluffy.", "author": {"@type": "Person", "name": "Cook"},
+            "recipeYield": ["4", "4 servings"], "prepTime": "PT10M", "cookTime": "PT1H05M",
+            "recipeIngredient": ["2 cups flour", " 1  egg "],
+            "recipeInstructions": [
+                {"@type": "HowToSection", "name": "Batter", "itemListElement": [{"@type": "HowToStep", "text": "Mix."}, {"@type": "HowToStep", "text": "<p>Rest.</p>"}]},
+                {"@type": "HowToSection", "name": "Cook", "itemListElement": [{"@type": "HowToStep", "text": "Fry."}]}],
+            "recipeCuisine": "American", "keywords": "breakfast, easy"}]}</script>"#;
+        let recipe = from_entities(&jsonld::entities(&Html::parse_document(html))).expect("expected a recipe");
+        let capture = capture(&recipe);
+        assert_eq!(capture.title, "Pancakes & Syrup");
+        assert_eq!(
+            capture.description,
+            "Fluffy.\n\n- **Yield:** 4 servings\n- **Prep time:** 10 min\n- **Cook time:** 1 h 5 min\n\n## Ingredients\n- [ ] 2 cups flour\n- [ ] 1 egg\n\n## Instructions\n### Batter\n1. Mix.\n2. Rest.\n\n### Cook\n1. Fry."
+        );
+        assert_eq!(capture.tags, vec!["breakfast".to_string(), "easy".to_string(), "American".to_string()]);
+        assert_eq!(capture.authors, vec!["Cook".to_string()]);
+    }
+
+    #[test]
+    fn test_plain_instructions_and_durations() {
+        assert_eq!(sections(&Value::String("Boil water.\nAdd pasta.".into())), vec![Section {
+            name: String::new(),
+            steps: vec!["Boil water.".into(), "Add pasta.".into()],
+        }]);
+        assert_eq!(duration("P1DT2H"), "1 d 2 h");
+        assert_eq!(duration("PT0M"), "");
+        assert_eq!(duration("20 minutes"), "20 minutes");
+    }
+}
@@ -0,0 +1,443 @@
+use eyre::{eyre, Result};
+use log::{debug, error};
+use scraper::{Html, Selector};
+use url::Url;
+
+use crate::{content, http, Capture};
+
+/// Rule names handled by this module.
+pub const RULE_NAMES: &[&str] = &["mastodon", "bluesky", "x"];
+
+/// Titles are built from the start of the post.
+const TITLE_LENGTH: usize = 60;
+/// How many parent posts of a reply are shown for context.
+const MAX_THREAD_DEPTH: usize = 10;
+
+#[derive(Debug, PartialEq)]
+pub enum PostUrl {
+    /// `url` is kept for servers whose Mastodon API is closed or missing.
+    Mastodon { instance: String, id: String, url: String },
+    Bluesky { actor: String, rkey: String },
+    X(String),
+}
+
+#[derive(Debug, Default)]
+pub struct Media {
+    pub url: String,
+    pub description: String,
+    /// Only a link to the media is known (X's `pic.twitter.com`), so it can't be shown inline.
+    pub link_only: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Post {
+    pub platform: String,
+    pub author_name: String,
+    pub handle: String,
+    pub author_url: String,
+    pub url: String,
+    pub text: String,
+    pub published: String,
+    pub media: Vec<Media>,
+    /// Handles the post replies to, when the parent posts themselves aren't available.
+    pub reply_to: Vec<String>,
+}
+
+pub fn parse_url(url: &str) -> Option<PostUrl> {
+    let parsed = Url::parse(url).ok()?;
+    let host = parsed.host_str()?.trim_start_matches("www.").trim_start_matches("mobile.");
+    let segments: Vec<&str> = parsed.path_segments()?.filter(|s| !s.is_empty()).collect();
+
+    match (host, segments.as_slice()) {
+        ("bsky.app", ["profile", actor, "post", rkey]) => Some(PostUrl::Bluesky {
+            actor: actor.to_string(),
+            rkey: rkey.to_string(),
+        }),
+        ("x.com" | "twitter.com", [_, "status", id, ..]) if id.chars().all(|c| c.is_ascii_digit()) => {
+            Some(PostUrl::X(format!("https://twitter.com/i/status/{id}")))
+        }
+        // Mastodon, and other ActivityPub servers that mirror its URL layout.
+        (instance, [user, id]) if user.starts_with('@') && id.chars().all(|c| c.is_ascii_digit()) => Some(PostUrl::Mastodon {
+            instance: instance.to_string(),
+            id: id.to_string(),
+            url: url.to_string(),
+        }),
+        (instance, ["users", _, "statuses", id]) if id.chars().all(|c| c.is_ascii_digit()) => Some(PostUrl::Mastodon {
+            instance: instance.to_string(),
+            id: id.to_string(),
+            url: url.to_string(),
+        }),
+        _ => None,
+    }
+}
+
+fn str_field(value: &serde_json::Value) -> String {
+    value.as_str().unwrap_or_default().to_string()
+}
+
+/// Returns the post followed by the posts it replies to, nearest first.
+pub async fn fetch(post_url: &PostUrl) -> Result<(Post, Vec<Post>)> {
+    debug!("fetch: post_url={:?}", post_url);
+    match post_url {
+        PostUrl::Mastodon { instance, id, url } => {
+            let status = match http::get_json(&format!("https://{instance}/api/v1/statuses/{id}")).await {
+                Ok(status) => status,
+                Err(e) => {
+                    // Servers with authorized fetch, or that only mirror Mastodon's URLs, still serve the ActivityPub object.
+                    debug!("fetch: Mastodon API failed for {}, trying ActivityPub: {:?}", url, e);
+                    return Ok((fetch_activitypub(url).await?, Vec::new()));
+                }
+            };
+            let ancestors = match http::get_json(&format!("https://{instance}/api/v1/statuses/{id}/context")).await {
+                Ok(context) => context["ancestors"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .rev()
+                    .take(MAX_THREAD_DEPTH)
+                    .map(parse_mastodon)
+                    .collect(),
+                Err(e) => {
+                    error!("Failed to fetch Mastodon thread context for {}: {:?}", id, e);
+                    Vec::new()
+                }
+            };
+            Ok((parse_mastodon(&status), ancestors))
+        }
+        PostUrl::Bluesky { actor, rkey } => {
+            // Post URIs are keyed by DID; profile URLs usually show the handle.
+            let did = if actor.starts_with("did:") {
+                actor.clone()
+            } else {
+                let resolve = Url::parse_with_params(
+                    "https://public.api.bsky.app/xrpc/com.atproto.identity.resolveHandle",
+                    &[("handle", actor)],
+                )?;
+                str_field(&http::get_json(resolve.as_str()).await?["did"])
+            };
+            let endpoint = Url::parse_with_params(
+                "https://public.api.bsky.app/xrpc/app.bsky.feed.getPostThread",
+                &[
+                    ("uri", format!("at://{did}/app.bsky.feed.post/{rkey}")),
+                    ("depth", "0".to_string()),
+                    ("parentHeight", MAX_THREAD_DEPTH.to_string()),
+                ],
+            )?;
+            parse_bluesky_thread(&http::get_json(endpoint.as_str()).await?)
+        }
+        PostUrl::X(url) => {
+            let endpoint = Url::parse_with_params(
+                "https://publish.twitter.com/oembed",
+                &[("url", url.as_str()), ("omit_script", "true"), ("dnt", "true")],
+            )?;
+            Ok((parse_x_oembed(&http::get_json(endpoint.as_str()).await?, url), Vec::new()))
+        }
+    }
+}
+
+async fn get_activity(url: &str) -> Result<serde_json::Value> {
+    let response = http::send(http::client().get(url).header("Accept", "application/activity+json")).await?;
+    if !response.status().is_success() {
+        return Err(eyre!("Request to {} failed with status {}", url, response.status()));
+    }
+    http::json(response).await
+}
+
+/// `attributedTo` and `url` may be a plain IRI, an object with one, or a list of either.
+fn iri(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Array(values) => values.first().map(iri).unwrap_or_default(),
+        serde_json::Value::Object(object) => object.get("href").or_else(|| object.get("id")).map(iri).unwrap_or_default(),
+        _ => str_field(value),
+    }
+}
+
+async fn fetch_activitypub(url: &str) -> Result<Post> {
+    let note = get_activity(url).await?;
+    let actor = match iri(&note["attributedTo"]) {
+        actor_url if actor_url.is_empty() => serde_json::Value::Null,
+        actor_url => get_activity(&actor_url).await.unwrap_or_else(|e| {
+            error!("Failed to fetch ActivityPub actor {}: {:?}", actor_url, e);
+            serde_json::Value::Null
+        }),
+    };
+    Ok(parse_activitypub(&note, &actor))
+}
+
+fn parse_activitypub(note: &serde_json::Value, actor: &serde_json::Value) -> Post {
+    let actor_id = match iri(&actor["id"]) {
+        id if id.is_empty() => iri(&note["attributedTo"]),
+        id => id,
+    };
+    let host = Url::parse(&actor_id).ok().and_then(|actor| actor.host_str().map(str::to_string)).unwrap_or_default();
+    let username = str_field(&actor["preferredUsername"]);
+    let author_url = match iri(&actor["url"]) {
+        url if url.is_empty() => actor_id,
+        url => url,
+    };
+    let url = match iri(&note["url"]) {
+        url if url.is_empty() => iri(&note["id"]),
+        url => url,
+    };
+    Post {
+        platform: "Mastodon".to_string(),
+        author_name: str_field(&actor["name"]),
+        handle: if username.is_empty() { String::new() } else { format!("@{username}@{host}") },
+        author_url,
+        url,
+        text: content::fragment_to_markdown(&str_field(&note["content"])),
+        published: str_field(&note["published"]),
+        media: note["attachment"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|attachment| Media {
+                url: iri(&attachment["url"]),
+                description: str_field(&attachment["name"]),
+                ..Media::default()
+            })
+            .collect(),
+        ..Post::default()
+    }
+}
+
+fn parse_mastodon(status: &serde_json::Value) -> Post {
+    let account = &status["account"];
+    Post {
+        platform: "Mastodon".to_string(),
+        author_name: str_field(&account["display_name"]),
+        handle: format!("@{}", str_field(&account["acct"])),
+        author_url: str_field(&account["url"]),
+        url: str_field(&status["url"]),
+        text: content::fragment_to_markdown(&str_field(&status["content"])),
+        published: str_field(&status["created_at"]),
+        media: status["media_attachments"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|media| Media {
+                url: str_field(&media["url"]),
+                description: str_field(&media["description"]),
+                ..Media::default()
+            })
+            .collect(),
+        ..Post::default()
+    }
+}
+
+fn parse_bluesky_post(post: &serde_json::Value) -> Post {
+    let author = &post["author"];
+    let handle = str_field(&author["handle"]);
+    let rkey = str_field(&post["uri"]).rsplit('/').next().unwrap_or_default().to_string();
+    let embed = &post["embed"];
+    // Quote posts with images nest the images under `media`.
+    let images = embed["images"].as_array().or_else(|| embed["media"]["images"].as_array());
+    let mut text = str_field(&post["record"]["text"]);
+    if let Some(external) = embed["external"].as_object() {
+        text.push_str(&format!(
+            "\n\n[{}]({})",
+            external.get("title").and_then(|title| title.as_str()).unwrap_or_default(),
+            external.get("uri").and_then(|uri| uri.as_str()).unwrap_or_default()
+        ));
+    }
+    Post {
+        platform: "Bluesky".to_string(),
+        author_name: str_field(&author["displayName"]),
+        handle: format!("@{handle}"),
+        author_url: format!("https://bsky.app/profile/{handle}"),
+        url: format!("https://bsky.app/profile/{handle}/post/{rkey}"),
+        text,
+        published: str_field(&post["record"]["createdAt"]),
+        media: images
+            .into_iter()
+            .flatten()
+            .map(|image| Media {
+                url: str_field(&image["fullsize"]),
+                description: str_field(&image["alt"]),
+                ..Media::default()
+            })
+            .collect(),
+        ..Post::default()
+    }
+}
+
+fn parse_bluesky_thread(response: &serde_json::Value) -> Result<(Post, Vec<Post>)> {
+    let thread = &response["thread"];
+    if thread["post"].is_null() {
+        return Err(eyre!("Bluesky post not found: {}", str_field(&response["message"])));
+    }
+    let mut ancestors = Vec::new();
+    let mut parent = &thread["parent"];
+    while !parent["post"].is_null() && ancestors.len() < MAX_THREAD_DEPTH {
+        ancestors.push(parse_bluesky_post(&parent["post"]));
+        parent = &parent["parent"];
+    }
+    Ok((parse_bluesky_post(&thread["post"]), ancestors))
+}
+
+/// oEmbed carries neither the parent posts nor the photo URLs, so replies only get the handles they
+/// start with and photos only their `pic.twitter.com` links; anything more needs the paid API.
+fn parse_x_oembed(oembed: &serde_json::Value, url: &str) -> Post {
+    let html = Html::parse_fragment(&str_field(&oembed["html"]));
+    let paragraph = Selector::parse("blockquote > p").expect("Invalid paragraph selector");
+    let link = Selector::parse("blockquote > a").expect("Invalid link selector");
+    let anchor = Selector::parse("a").expect("Invalid link selector");
+    let author_url = str_field(&oembed["author_url"]);
+
+    let mut text = String::new();
+    let mut media = Vec::new();
+    let mut reply_to = Vec::new();
+    if let Some(p) = html.select(&paragraph).next() {
+        let mut inner = p.inner_html();
+        for a in p.select(&anchor) {
+            let label = a.text().collect::<String>();
+            if label.starts_with("pic.twitter.com/") {
+                inner = inner.replace(&a.html(), "");
+                media.push(Media {
+                    url: format!("https://{label}"),
+                    description: label,
+                    link_only: true,
+                });
+            }
+        }
+        // Replies open with the handles being replied to.
+        reply_to = p.text().collect::<String>().split_whitespace().take_while(|word| word.starts_with('@')).map(str::to_string).collect();
+        text = content::fragment_to_markdown(&inner);
+    }
+    Post {
+        platform: "X".to_string(),
+        author_name: str_field(&oembed["author_name"]),
+        handle: author_url.rsplit('/').next().map(|handle| format!("@{handle}")).unwrap_or_default(),
+        author_url,
+        url: url.to_string(),
+        text,
+        // oEmbed only gives a display date, e.g. "March 21, 2006"; normalisation happens on write.
+        published: html
+            .select(&link)
+            .next_back()
+            .map(|a| a.text().collect::<String>())
+            .unwrap_or_default(),
+        media,
+        reply_to,
+    }
+}
+
+fn render_post(post: &Post) -> String {
+    let mut quoted = Vec::new();
+    if !post.reply_to.is_empty() {
+        quoted.push(format!("> *Replying to {}*", post.reply_to.join(" ")));
+        quoted.push(">".to_string());
+    }
+    quoted.extend(post.text.lines().map(|line| if line.is_empty() { ">".to_string() } else { format!("> {line}") }));
+    for media in &post.media {
+        let description = media.description.replace(['[', ']', '\n'], " ");
+        quoted.push(">".to_string());
+        quoted.push(if media.link_only { format!("> [{}]({})", description, media.url) } else { format!("> ![{}]({})", description, media.url) });
+    }
+    let name = if post.author_name.is_empty() { post.handle.clone() } else { format!("{} ({})", post.author_name, post.handle) };
+    quoted.push(">".to_string());
+    quoted.push(format!("> — [{}]({}) · [{}]({})", name, post.author_url, post.published, post.url));
+    quoted.join("\n")
+}
+
+pub fn capture(post: &Post, ancestors: &[Post]) -> Capture {
+    let mut description = render_post(post);
+    if !ancestors.is_empty() {
+        let thread = ancestors.iter().rev().map(render_post).collect::<Vec<_>>().join("\n\n");
+        description = format!("{description}\n\n## Thread\n{thread}");
+    }
+
+    let snippet: String = post.text.split_whitespace().collect::<Vec<_>>().join(" ").chars().take(TITLE_LENGTH).collect();
+    let author = if post.author_name.is_empty() { post.handle.clone() } else { post.author_name.clone() };
+    let mut extra = vec![
+        ("platform".to_string(), post.platform.clone()),
+        ("handle".to_string(), post.handle.clone()),
+    ];
+    if !post.reply_to.is_empty() {
+        extra.push(("in_reply_to".to_string(), post.reply_to.join(", ")));
+    }
+    Capture {
+        title: format!("{} - {}", author, snippet.trim()),
+        description,
+        authors: vec![author],
+        published: post.published.clone(),
+        extra,
+        ..Capture::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_post_url() {
+        let cases = [
+            ("https://mastodon.social/@Gargron/109318821117356215", Some(PostUrl::Mastodon { instance: "mastodon.social".into(), id: "109318821117356215".into(), url: "https://mastodon.social/@Gargron/109318821117356215".into() })),
+            ("https://hachyderm.io/users/someone/statuses/1234", Some(PostUrl::Mastodon { instance: "hachyderm.io".into(), id: "1234".into(), url: "https://hachyderm.io/users/someone/statuses/1234".into() })),
+            ("https://bsky.app/profile/jay.bsky.team/post/3k44deefqdk2g", Some(PostUrl::Bluesky { actor: "jay.bsky.team".into(), rkey: "3k44deefqdk2g".into() })),
+            ("https://x.com/jack/status/20?s=20", Some(PostUrl::X("https://twitter.com/i/status/20".into()))),
+            ("https://mastodon.social/@Gargron", None),
+            ("https://x.com/jack", None),
+        ];
+        for (url, expected) in cases {
+            assert_eq!(parse_url(url), expected, "url={url}");
+        }
+    }
+
+    #[test]
+    fn test_bluesky_thread_capture() -> Result<()> {
+        let author = json!({"handle": "alice.bsky.social", "displayName": "Alice"});
+        let response = json!({"thread": {
+            "post": {"uri": "at://did:plc:x/app.bsky.feed.post/3kb", "author": author, "record": {"text": "Reply\nsecond line", "createdAt": "2024-01-02T03:04:05Z"},
+                "embed": {"images": [{"fullsize": "https://cdn.bsky.app/img.jpg", "alt": "A cat"}]}},
+            "parent": {"post": {"uri": "at://did:plc:y/app.bsky.feed.post/3ka", "author": {"handle": "bob.bsky.social", "displayName": ""},
+                "record": {"text": "Original", "createdAt": "2024-01-01T00:00:00Z"}}}}});
+        let (post, ancestors) = parse_bluesky_thread(&response)?;
+        let capture = capture(&post, &ancestors);
+        assert_eq!(capture.title, "Alice - Reply second line");
+        assert_eq!(
+            capture.description,
+            "> Reply\n> second line\n>\n> ![A cat](https://cdn.bsky.app/img.jpg)\n>\n> — [Alice (@alice.bsky.social)](https://bsky.app/profile/alice.bsky.social) · [2024-01-02T03:04:05Z](https://bsky.app/profile/alice.bsky.social/post/3kb)\n\n## Thread\n> Original\n>\n> — [@bob.bsky.social](https://bsky.app/profile/bob.bsky.social) · [2024-01-01T00:00:00Z](https://bsky.app/profile/bob.bsky.social/post/3ka)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_x_oembed() {
+        let oembed = json!({"author_name": "jack", "author_url": "https://twitter.com/jack",
+            "html": "<blockquote class=\"twitter-tweet\"><p lang=\"en\" dir=\"ltr\">just setting up my twttr</p>&mdash; jack (@jack) <a href=\"https://twitter.com/jack/status/20\">March 21, 2006</a></blockquote>\n"});
+        let post = parse_x_oembed(&oembed, "https://twitter.com/i/status/20");
+        assert_eq!(post.text, "just setting up my twttr");
+        assert_eq!(post.handle, "@jack");
+        assert_eq!(post.published, "March 21, 2006");
+
+        let reply = json!({"author_name": "Bob", "author_url": "https://twitter.com/bob",
+            "html": "<blockquote class=\"twitter-tweet\"><p lang=\"en\" dir=\"ltr\"><a href=\"https://twitter.com/jack?ref_src=twsrc%5Etfw\">@jack</a> welcome! <a href=\"https://t.co/abc\">pic.twitter.com/abc</a></p>&mdash; Bob (@bob) <a href=\"https://twitter.com/bob/status/21\">March 22, 2006</a></blockquote>\n"});
+        let post = parse_x_oembed(&reply, "https://twitter.com/i/status/21");
+        assert_eq!(post.reply_to, vec!["@jack".to_string()]);
+        assert_eq!(post.media.len(), 1);
+        assert!(!post.text.contains("pic.twitter.com"), "{}", post.text);
+        let capture = capture(&post, &[]);
+        assert!(capture.description.starts_with("> *Replying to @jack*\n>\n> "), "{}", capture.description);
+        assert!(capture.description.contains("> [pic.twitter.com/abc](https://pic.twitter.com/abc)"), "{}", capture.description);
+        assert!(capture.extra.contains(&("in_reply_to".to_string(), "@jack".to_string())));
+    }
+
+    #[test]
+    fn test_parse_activitypub_note() {
+        let note = json!({"id": "https://social.example/objects/1", "url": "https://social.example/notice/1",
+            "attributedTo": "https://social.example/users/alice", "content": "<p>Hello <b>fediverse</b></p>", "published": "2024-01-02T03:04:05Z",
+            "attachment": [{"type": "Document", "mediaType": "image/png", "url": "https://social.example/media/1.png", "name": "A cat"}]});
+        let actor = json!({"id": "https://social.example/users/alice", "preferredUsername": "alice", "name": "Alice",
+            "url": "https://social.example/@alice"});
+        let post = parse_activitypub(&note, &actor);
+        assert_eq!(post.handle, "@alice@social.example");
+        assert_eq!(post.author_url, "https://social.example/@alice");
+        assert_eq!(post.url, "https://social.example/notice/1");
+        assert_eq!(post.text, "Hello **fediverse**");
+        assert_eq!(post.media[0].url, "https://social.example/media/1.png");
+        assert_eq!(parse_activitypub(&note, &serde_json::Value::Null).author_url, "https://social.example/users/alice");
+    }
+}
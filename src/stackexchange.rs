@@ -0,0 +1,238 @@
+use chrono::DateTime;
+use eyre::{eyre, Result};
+use log::debug;
+use url::Url;
+
+use crate::{content, http, xml, Capture, STACKEXCHANGE_KEY};
+
+const API_URL: &str = "https://api.stackexchange.com/2.3";
+/// Stack Exchange sites that don't live under stackexchange.com.
+const SITE_DOMAINS: &[&str] = &[
+    "stackoverflow.com",
+    "serverfault.com",
+    "superuser.com",
+    "askubuntu.com",
+    "mathoverflow.net",
+    "stackapps.com",
+    "stackexchange.com",
+];
+
+#[derive(Debug, PartialEq)]
+pub enum Post {
+    Question { site: String, id: u64 },
+    Answer { site: String, id: u64 },
+}
+
+#[derive(Debug, Default)]
+struct Answer {
+    id: u64,
+    author: String,
+    body: String,
+    score: i64,
+    accepted: bool,
+}
+
+#[derive(Debug, Default)]
+struct Question {
+    site: String,
+    title: String,
+    author: String,
+    body: String,
+    tags: Vec<String>,
+    score: i64,
+    answer_count: u64,
+    accepted_answer_id: Option<u64>,
+    published: String,
+    link: String,
+}
+
+pub fn parse_url(url: &str) -> Option<Post> {
+    let parsed = Url::parse(url).ok()?;
+    let host = parsed.host_str()?;
+    // The API takes a site's domain as its `site` parameter.
+    if !SITE_DOMAINS.iter().any(|domain| host == *domain || host.ends_with(&format!(".{domain}"))) {
+        return None;
+    }
+    let segments: Vec<&str> = parsed.path_segments()?.filter(|s| !s.is_empty()).collect();
+    let site = host.to_string();
+    match segments.as_slice() {
+        // `/questions/{id}/{slug}/{answer id}` is where "share" on an answer used to point.
+        ["questions", _, _, answer, ..] if answer.parse::<u64>().is_ok() => Some(Post::Answer {
+            site,
+            id: answer.parse().ok()?,
+        }),
+        ["questions", id, ..] | ["q", id, ..] => Some(Post::Question { site, id: id.parse().ok()? }),
+        ["a", id, ..] => Some(Post::Answer { site, id: id.parse().ok()? }),
+        _ => None,
+    }
+}
+
+async fn get_items(path: &str, site: &str, params: &[(&str, &str)]) -> Result<Vec<serde_json::Value>> {
+    let mut query = vec![("site", site), ("filter", "withbody")];
+    query.extend_from_slice(params);
+    if let Some(key) = STACKEXCHANGE_KEY.as_deref() {
+        query.push(("key", key));
+    }
+    let url = Url::parse_with_params(&format!("{API_URL}{path}"), &query)?;
+    let response = http::get(url.as_str()).await?;
+    let status = response.status();
+    let body: serde_json::Value = http::json(response).await?;
+    if !status.is_success() {
+        return Err(eyre!(
+            "Stack Exchange request to {} failed with status {}: {}",
+            path,
+            status,
+            body["error_message"].as_str().unwrap_or_default()
+        ));
+    }
+    Ok(body["items"].as_array().cloned().unwrap_or_default())
+}
+
+fn str_field(value: &serde_json::Value) -> String {
+    xml::unescape(value.as_str().unwrap_or_default())
+}
+
+fn parse_question(item: &serde_json::Value, site: &str) -> Question {
+    Question {
+        site: site.to_string(),
+        title: str_field(&item["title"]),
+        author: str_field(&item["owner"]["display_name"]),
+        body: content::fragment_to_markdown(item["body"].as_str().unwrap_or_default()),
+        tags: item["tags"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|tag| tag.as_str().map(str::to_string))
+            .collect(),
+        score: item["score"].as_i64().unwrap_or_default(),
+        answer_count: item["answer_count"].as_u64().unwrap_or_default(),
+        accepted_answer_id: item["accepted_answer_id"].as_u64(),
+        published: item["creation_date"]
+            .as_i64()
+            .and_then(|seconds| DateTime::from_timestamp(seconds, 0))
+            .map(|date| date.to_rfc3339())
+            .unwrap_or_default(),
+        link: item["link"].as_str().unwrap_or_default().to_string(),
+    }
+}
+
+fn parse_answer(item: &serde_json::Value) -> Answer {
+    Answer {
+        id: item["answer_id"].as_u64().unwrap_or_default(),
+        author: str_field(&item["owner"]["display_name"]),
+        body: content::fragment_to_markdown(item["body"].as_str().unwrap_or_default()),
+        score: item["score"].as_i64().unwrap_or_default(),
+        accepted: item["is_accepted"].as_bool() == Some(true),
+    }
+}
+
+async fn get_answer(site: &str, id: u64) -> Result<serde_json::Value> {
+    get_items(&format!("/answers/{id}"), site, &[])
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| eyre!("Stack Exchange answer {} not found on {}", id, site))
+}
+
+pub async fn fetch(post: &Post) -> Result<Capture> {
+    debug!("fetch: post={:?}", post);
+    let (site, question_id, answer) = match post {
+        Post::Question { site, id } => (site, *id, None),
+        Post::Answer { site, id } => {
+            let item = get_answer(site, *id).await?;
+            let question_id = item["question_id"].as_u64().ok_or_else(|| eyre!("Answer {} has no question", id))?;
+            (site, question_id, Some(parse_answer(&item)))
+        }
+    };
+
+    let item = get_items(&format!("/questions/{question_id}"), site, &[])
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| eyre!("Stack Exchange question {} not found on {}", question_id, site))?;
+    let question = parse_question(&item, site);
+
+    // The accepted answer isn't necessarily among the highest-voted ones, so ask for it by id.
+    let answer = match (answer, question.accepted_answer_id) {
+        (Some(answer), _) => Some(answer),
+        (None, Some(accepted)) => Some(parse_answer(&get_answer(site, accepted).await?)),
+        (None, None) => {
+            let path = format!("/questions/{question_id}/answers");
+            get_items(&path, site, &[("sort", "votes"), ("order", "desc"), ("pagesize", "1")]).await?.first().map(parse_answer)
+        }
+    };
+    Ok(capture(question, answer))
+}
+
+fn capture(question: Question, answer: Option<Answer>) -> Capture {
+    let mut description = question.body.clone();
+    if let Some(answer) = &answer {
+        let heading = if answer.accepted { "Accepted answer" } else { "Top answer" };
+        description.push_str(&format!(
+            "\n\n## {heading}\n{}\n\n— **{}** ({} points) · [link](https://{}/a/{})",
+            answer.body, answer.author, answer.score, question.site, answer.id
+        ));
+    }
+
+    let mut extra = vec![
+        ("site".to_string(), question.site),
+        ("score".to_string(), question.score.to_string()),
+        ("answers".to_string(), question.answer_count.to_string()),
+    ];
+    if !question.link.is_empty() {
+        extra.push(("question_url".to_string(), question.link));
+    }
+
+    Capture {
+        title: question.title,
+        description,
+        authors: vec![question.author],
+        tags: question.tags,
+        published: question.published,
+        extra,
+        ..Capture::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_stackexchange_url() {
+        let site = |site: &str| site.to_string();
+        let cases = [
+            ("https://stackoverflow.com/questions/11227809/why-is-it-faster", Some(Post::Question { site: site("stackoverflow.com"), id: 11227809 })),
+            ("https://unix.stackexchange.com/q/123", Some(Post::Question { site: site("unix.stackexchange.com"), id: 123 })),
+            ("https://superuser.com/a/456/789", Some(Post::Answer { site: site("superuser.com"), id: 456 })),
+            ("https://stackoverflow.com/questions/1/title/42#42", Some(Post::Answer { site: site("stackoverflow.com"), id: 42 })),
+            ("https://stackoverflow.com/questions/tagged/rust", None),
+            ("https://example.com/questions/1", None),
+        ];
+        for (url, expected) in cases {
+            assert_eq!(parse_url(url), expected, "url={url}");
+        }
+    }
+
+    #[test]
+    fn test_stackexchange_capture() {
+        let question = parse_question(
+            &json!({"title": "Why isn&#39;t this &quot;fast&quot;?", "owner": {"display_name": "asker"},
+                "body": "<p>Slow:</p>\n<pre class=\"lang-rust\"><code>let x = 1;\n</code></pre>",
+                "tags": ["rust", "performance"], "score": 10, "answer_count": 2, "accepted_answer_id": 3, "creation_date": 1700000000,
+                "link": "https://stackoverflow.com/questions/1/why"}),
+            "stackoverflow.com",
+        );
+        assert_eq!(question.accepted_answer_id, Some(3));
+        let answer = json!({"answer_id": 3, "owner": {"display_name": "helper"}, "body": "<p>Use <code>--release</code>.</p>", "score": 5, "is_accepted": true});
+        let capture = capture(question, Some(parse_answer(&answer)));
+        assert_eq!(capture.title, "Why isn't this \"fast\"?");
+        assert_eq!(
+            capture.description,
+            "Slow:\n\n```rust\nlet x = 1;\n```\n\n## Accepted answer\nUse `--release`.\n\n— **helper** (5 points) · [link](https://stackoverflow.com/a/3)"
+        );
+        assert_eq!(capture.tags, vec!["rust".to_string(), "performance".to_string()]);
+        assert_eq!(capture.published, "2023-11-14T22:13:20+00:00");
+    }
+}
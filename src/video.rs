@@ -0,0 +1,356 @@
+use chrono::DateTime;
+use eyre::{eyre, Result};
+use lazy_static::lazy_static;
+use log::debug;
+use regex::Regex;
+use scraper::{Html, Selector};
+use url::Url;
+
+use crate::{http, TWITCH_ACCESS_TOKEN, TWITCH_CLIENT_ID};
+
+lazy_static! {
+    static ref TWITCH_DURATION_RE: Regex =
+        Regex::new(r"^(?:(\d+)h)?(?:(\d+)m)?(?:(\d+)s)?$").expect("Invalid Twitch duration regex");
+}
+
+/// Obsidian's webview is served from this host, which Twitch requires as the embed parent.
+const TWITCH_EMBED_PARENT: &str = "obsidian.md";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VideoPlatform {
+    Vimeo,
+    Twitch,
+    Dailymotion,
+    PeerTube,
+}
+
+#[derive(Debug, Default)]
+pub struct PlatformVideo {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub channel: String,
+    pub duration: Option<u64>,
+    pub published: String,
+    pub thumbnail: String,
+    pub tags: Vec<String>,
+    pub embed_url: String,
+}
+
+#[derive(Debug, PartialEq)]
+enum TwitchUrl {
+    Video(String),
+    Clip(String),
+}
+
+pub async fn fetch_video(platform: VideoPlatform, url: &str) -> Result<PlatformVideo> {
+    debug!("fetch_video: platform={:?} url={}", platform, url);
+    match platform {
+        VideoPlatform::Vimeo => fetch_vimeo(url).await,
+        VideoPlatform::Twitch => fetch_twitch(url).await,
+        VideoPlatform::Dailymotion => fetch_dailymotion(url).await,
+        VideoPlatform::PeerTube => fetch_peertube(url).await,
+    }
+}
+
+pub fn format_duration(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds % 3600 / 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
+fn str_field(value: &serde_json::Value) -> String {
+    value.as_str().unwrap_or_default().to_string()
+}
+
+async fn fetch_vimeo(url: &str) -> Result<PlatformVideo> {
+    let endpoint = Url::parse_with_params("https://vimeo.com/api/oembed.json", &[("url", url)])?;
+    let oembed = http::get_json(endpoint.as_str()).await?;
+    let id = oembed["video_id"]
+        .as_u64()
+        .map(|id| id.to_string())
+        .ok_or_else(|| eyre!("Vimeo oEmbed response has no video_id for {}", url))?;
+
+    Ok(PlatformVideo {
+        embed_url: format!("https://player.vimeo.com/video/{id}"),
+        id,
+        title: str_field(&oembed["title"]),
+        description: str_field(&oembed["description"]),
+        channel: str_field(&oembed["author_name"]),
+        duration: oembed["duration"].as_u64(),
+        published: str_field(&oembed["upload_date"]),
+        thumbnail: str_field(&oembed["thumbnail_url"]),
+        tags: Vec::new(),
+    })
+}
+
+fn parse_twitch_url(url: &str) -> Option<TwitchUrl> {
+    let parsed = Url::parse(url).ok()?;
+    let host = parsed.host_str()?.trim_start_matches("www.").trim_start_matches("m.");
+    let segments: Vec<&str> = parsed.path_segments()?.filter(|s| !s.is_empty()).collect();
+    match (host, segments.as_slice()) {
+        ("twitch.tv", ["videos", id, ..]) => Some(TwitchUrl::Video(id.to_string())),
+        ("twitch.tv", [_, "clip", slug, ..]) => Some(TwitchUrl::Clip(slug.to_string())),
+        ("clips.twitch.tv", ["embed", ..]) => parsed
+            .query_pairs()
+            .find(|(k, _)| k == "clip")
+            .map(|(_, v)| TwitchUrl::Clip(v.into_owned())),
+        ("clips.twitch.tv", [slug, ..]) => Some(TwitchUrl::Clip(slug.to_string())),
+        _ => None,
+    }
+}
+
+fn parse_twitch_duration(duration: &str) -> Option<u64> {
+    let caps = TWITCH_DURATION_RE.captures(duration)?;
+    let part = |i| caps.get(i).and_then(|m| m.as_str().parse::<u64>().ok()).unwrap_or(0);
+    Some(part(1) * 3600 + part(2) * 60 + part(3))
+}
+
+async fn fetch_twitch(url: &str) -> Result<PlatformVideo> {
+    let twitch_url = parse_twitch_url(url).ok_or_else(|| eyre!("Failed to extract Twitch video or clip from URL"))?;
+    let (id, embed_url) = match &twitch_url {
+        TwitchUrl::Video(id) => (
+            id.clone(),
+            format!("https://player.twitch.tv/?video={id}&parent={TWITCH_EMBED_PARENT}&autoplay=false"),
+        ),
+        TwitchUrl::Clip(slug) => (
+            slug.clone(),
+            format!("https://clips.twitch.tv/embed?clip={slug}&parent={TWITCH_EMBED_PARENT}&autoplay=false"),
+        ),
+    };
+
+    let mut video = match (TWITCH_CLIENT_ID.as_deref(), TWITCH_ACCESS_TOKEN.as_deref()) {
+        (Some(client_id), Some(token)) => fetch_twitch_helix(&twitch_url, client_id, token).await?,
+        _ => {
+            debug!("Twitch credentials not set; reading page metadata for {}", url);
+            fetch_twitch_page(url).await?
+        }
+    };
+    video.id = id;
+    video.embed_url = embed_url;
+    Ok(video)
+}
+
+async fn fetch_twitch_helix(twitch_url: &TwitchUrl, client_id: &str, token: &str) -> Result<PlatformVideo> {
+    let endpoint = match twitch_url {
+        TwitchUrl::Video(id) => format!("https://api.twitch.tv/helix/videos?id={id}"),
+        TwitchUrl::Clip(slug) => format!("https://api.twitch.tv/helix/clips?id={slug}"),
+    };
+    let response = http::send(
+        http::client()
+            .get(&endpoint)
+            .header("Client-Id", client_id)
+            .header("Authorization", format!("Bearer {token}")),
+    )
+    .await?;
+    let response: serde_json::Value = http::json(response).await?;
+
+    let data = &response["data"][0];
+    if data.is_null() {
+        return Err(eyre!("Twitch metadata not found for {:?}", twitch_url));
+    }
+
+    let (channel, duration, published) = match twitch_url {
+        TwitchUrl::Video(_) => (
+            str_field(&data["user_name"]),
+            data["duration"].as_str().and_then(parse_twitch_duration),
+            str_field(&data["published_at"]),
+        ),
+        TwitchUrl::Clip(_) => (
+            str_field(&data["broadcaster_name"]),
+            data["duration"].as_f64().map(|secs| secs.round() as u64),
+            str_field(&data["created_at"]),
+        ),
+    };
+
+    Ok(PlatformVideo {
+        title: str_field(&data["title"]),
+        description: str_field(&data["description"]),
+        channel,
+        duration,
+        published,
+        thumbnail: str_field(&data["thumbnail_url"])
+            .replace("%{width}", "1280")
+            .replace("%{height}", "720"),
+        ..Default::default()
+    })
+}
+
+/// `twitch.tv/<channel>/clip/<slug>` names the channel; `clips.twitch.tv/<slug>` and `twitch.tv/videos/<id>` don't,
+/// so those only get one from Helix.
+fn channel_from_url(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .filter(|u| u.host_str().is_some_and(|host| host.trim_start_matches("www.").trim_start_matches("m.") == "twitch.tv"))
+        .and_then(|u| u.path_segments().and_then(|mut s| s.next().map(str::to_string)))
+        .filter(|segment| segment != "videos")
+        .unwrap_or_default()
+}
+
+async fn fetch_twitch_page(url: &str) -> Result<PlatformVideo> {
+    let content = http::text(http::get(url).await?).await?;
+    let document = Html::parse_document(&content);
+    let meta = |property: &str| -> Result<String> {
+        let selector = Selector::parse(&format!("meta[property='{property}']"))
+            .map_err(|e| eyre!("Failed to compile selector: {}", e))?;
+        Ok(document
+            .select(&selector)
+            .next()
+            .and_then(|e| e.value().attr("content"))
+            .unwrap_or_default()
+            .to_string())
+    };
+
+    let channel = channel_from_url(url);
+
+    Ok(PlatformVideo {
+        title: meta("og:title")?,
+        description: meta("og:description")?,
+        channel,
+        duration: meta("og:video:duration")?.parse().ok(),
+        published: meta("og:video:release_date")?,
+        thumbnail: meta("og:image")?,
+        ..Default::default()
+    })
+}
+
+fn parse_dailymotion_id(url: &str) -> Option<String> {
+    let parsed = Url::parse(url).ok()?;
+    let host = parsed.host_str()?.trim_start_matches("www.");
+    let segments: Vec<&str> = parsed.path_segments()?.filter(|s| !s.is_empty()).collect();
+    let id = match (host, segments.as_slice()) {
+        ("dai.ly", [id, ..]) => *id,
+        ("dailymotion.com", ["video", id, ..]) => *id,
+        ("dailymotion.com", ["embed", "video", id, ..]) => *id,
+        _ => return None,
+    };
+    // Older share links append the slug to the id: x7tgad0_some-title
+    id.split('_').next().map(str::to_string)
+}
+
+async fn fetch_dailymotion(url: &str) -> Result<PlatformVideo> {
+    let id = parse_dailymotion_id(url).ok_or_else(|| eyre!("Failed to extract Dailymotion video ID from URL"))?;
+    let data = http::get_json(&format!(
+        "https://api.dailymotion.com/video/{id}?fields=id,title,description,owner.screenname,duration,created_time,thumbnail_720_url,tags"
+    ))
+    .await?;
+
+    Ok(PlatformVideo {
+        embed_url: format!("https://www.dailymotion.com/embed/video/{id}"),
+        id,
+        title: str_field(&data["title"]),
+        description: str_field(&data["description"]),
+        channel: str_field(&data["owner.screenname"]),
+        duration: data["duration"].as_u64(),
+        published: data["created_time"]
+            .as_i64()
+            .and_then(|ts| DateTime::from_timestamp(ts, 0))
+            .map(|dt| dt.to_rfc3339())
+            .unwrap_or_default(),
+        thumbnail: str_field(&data["thumbnail_720_url"]),
+        tags: data["tags"]
+            .as_array()
+            .map(|tags| tags.iter().filter_map(|t| t.as_str()).map(String::from).collect())
+            .unwrap_or_default(),
+    })
+}
+
+pub fn parse_peertube_url(url: &str) -> Option<(String, String)> {
+    let parsed = Url::parse(url).ok()?;
+    let origin = parsed.origin().ascii_serialization();
+    let segments: Vec<&str> = parsed.path_segments()?.filter(|s| !s.is_empty()).collect();
+    let id = match segments.as_slice() {
+        ["w", id, ..] => *id,
+        ["videos", "watch" | "embed", id, ..] => *id,
+        _ => return None,
+    };
+    Some((origin, id.to_string()))
+}
+
+/// `/w/` and `/videos/watch/` paths exist on plenty of sites; only a PeerTube instance answers its video API,
+/// so this fails for anything else.
+async fn fetch_peertube(url: &str) -> Result<PlatformVideo> {
+    let (origin, id) = parse_peertube_url(url).ok_or_else(|| eyre!("Failed to extract PeerTube video ID from URL"))?;
+    let data = http::get_json(&format!("{origin}/api/v1/videos/{id}")).await?;
+    let uuid = data["uuid"]
+        .as_str()
+        .ok_or_else(|| eyre!("{} did not answer like a PeerTube instance", origin))?
+        .to_string();
+
+    let channel = ["channel", "account"]
+        .iter()
+        .map(|owner| str_field(&data[owner]["displayName"]))
+        .find(|name| !name.is_empty())
+        .unwrap_or_default();
+    let embed_path = data["embedPath"]
+        .as_str()
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("/videos/embed/{id}"));
+
+    Ok(PlatformVideo {
+        id: uuid,
+        title: str_field(&data["name"]),
+        description: str_field(&data["description"]),
+        channel,
+        duration: data["duration"].as_u64(),
+        published: str_field(&data["publishedAt"]),
+        thumbnail: data["thumbnailPath"]
+            .as_str()
+            .map(|path| format!("{origin}{path}"))
+            .unwrap_or_default(),
+        tags: data["tags"]
+            .as_array()
+            .map(|tags| tags.iter().filter_map(|t| t.as_str()).map(String::from).collect())
+            .unwrap_or_default(),
+        embed_url: format!("{origin}{embed_path}"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_video_urls() {
+        assert_eq!(
+            parse_twitch_url("https://www.twitch.tv/videos/1234567890"),
+            Some(TwitchUrl::Video("1234567890".to_string()))
+        );
+        assert_eq!(
+            parse_twitch_url("https://www.twitch.tv/somechannel/clip/FunnyClipSlug-abc123"),
+            Some(TwitchUrl::Clip("FunnyClipSlug-abc123".to_string()))
+        );
+        assert_eq!(
+            parse_twitch_url("https://clips.twitch.tv/FunnyClipSlug"),
+            Some(TwitchUrl::Clip("FunnyClipSlug".to_string()))
+        );
+        assert_eq!(parse_twitch_url("https://www.twitch.tv/somechannel"), None);
+        assert_eq!(channel_from_url("https://www.twitch.tv/somechannel/clip/FunnyClipSlug-abc123"), "somechannel");
+        assert_eq!(channel_from_url("https://clips.twitch.tv/FunnyClipSlug"), "");
+        assert_eq!(channel_from_url("https://www.twitch.tv/videos/1234567890"), "");
+
+        assert_eq!(parse_dailymotion_id("https://www.dailymotion.com/video/x7tgad0"), Some("x7tgad0".to_string()));
+        assert_eq!(parse_dailymotion_id("https://www.dailymotion.com/video/x2abc_some-title"), Some("x2abc".to_string()));
+        assert_eq!(parse_dailymotion_id("https://dai.ly/x7tgad0"), Some("x7tgad0".to_string()));
+
+        assert_eq!(
+            parse_peertube_url("https://framatube.org/w/9c9de5e8-0a1e-484a-b099-e80766180a6d"),
+            Some(("https://framatube.org".to_string(), "9c9de5e8-0a1e-484a-b099-e80766180a6d".to_string()))
+        );
+        assert_eq!(
+            parse_peertube_url("https://peertube.example/videos/watch/abc123?start=10"),
+            Some(("https://peertube.example".to_string(), "abc123".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_durations() {
+        assert_eq!(parse_twitch_duration("3h8m33s"), Some(11313));
+        assert_eq!(parse_twitch_duration("45s"), Some(45));
+        assert_eq!(format_duration(11313), "3:08:33");
+        assert_eq!(format_duration(95), "1:35");
+    }
+}
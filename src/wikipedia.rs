@@ -0,0 +1,162 @@
+use eyre::Result;
+use log::{debug, error};
+use url::Url;
+
+use crate::{http, Capture};
+
+#[derive(Debug, PartialEq)]
+pub struct ArticleUrl {
+    pub language: String,
+    /// Still percent-encoded, as the REST API expects it in the path.
+    pub title: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Article {
+    pub title: String,
+    pub description: String,
+    pub extract: String,
+    pub thumbnail: String,
+    pub wikidata: String,
+    pub language: String,
+    pub categories: Vec<String>,
+}
+
+pub fn parse_url(url: &str) -> Option<ArticleUrl> {
+    let parsed = Url::parse(url).ok()?;
+    let host = parsed.host_str()?;
+    let language = host.strip_suffix(".wikipedia.org")?.trim_end_matches(".m");
+    if language.is_empty() || language == "www" || language.contains('.') {
+        return None;
+    }
+    let title = parsed.path().strip_prefix("/wiki/")?;
+    if title.is_empty() {
+        return None;
+    }
+    Some(ArticleUrl {
+        language: language.to_string(),
+        // Titles like AC/DC keep their slash in the page URL but are a single REST path segment.
+        title: title.replace('/', "%2F"),
+    })
+}
+
+fn str_field(value: &serde_json::Value) -> String {
+    value.as_str().unwrap_or_default().to_string()
+}
+
+pub async fn fetch(article_url: &ArticleUrl) -> Result<Article> {
+    debug!("fetch: article_url={:?}", article_url);
+    let ArticleUrl { language, title } = article_url;
+    let summary = http::get_json(&format!("https://{language}.wikipedia.org/api/rest_v1/page/summary/{title}")).await?;
+
+    // Hidden categories are maintenance bookkeeping ("Articles with short description"), not topics.
+    let categories_url = Url::parse_with_params(
+        &format!("https://{language}.wikipedia.org/w/api.php"),
+        &[
+            ("action", "query"),
+            ("prop", "categories"),
+            ("clshow", "!hidden"),
+            ("cllimit", "50"),
+            ("format", "json"),
+            ("formatversion", "2"),
+            ("titles", &str_field(&summary["titles"]["canonical"])),
+        ],
+    )?;
+    let categories = match http::get_json(categories_url.as_str()).await {
+        Ok(response) => parse_categories(&response),
+        Err(e) => {
+            error!("Failed to fetch Wikipedia categories for {}: {:?}", title, e);
+            Vec::new()
+        }
+    };
+
+    Ok(parse_summary(&summary, language, categories))
+}
+
+fn parse_summary(summary: &serde_json::Value, language: &str, categories: Vec<String>) -> Article {
+    Article {
+        title: str_field(&summary["title"]),
+        description: str_field(&summary["description"]),
+        extract: str_field(&summary["extract"]),
+        thumbnail: str_field(&summary["thumbnail"]["source"]),
+        wikidata: str_field(&summary["wikibase_item"]),
+        language: match str_field(&summary["lang"]) {
+            lang if lang.is_empty() => language.to_string(),
+            lang => lang,
+        },
+        categories,
+    }
+}
+
+fn parse_categories(response: &serde_json::Value) -> Vec<String> {
+    response["query"]["pages"][0]["categories"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|category| category["title"].as_str())
+        // The namespace prefix is localised ("Category:", "Kategorie:", ...).
+        .map(|title| title.split_once(':').map_or(title, |(_, name)| name).to_string())
+        .collect()
+}
+
+pub fn capture(article: &Article) -> Capture {
+    let description = if article.description.is_empty() {
+        article.extract.clone()
+    } else {
+        format!("*{}*\n\n{}", article.description, article.extract)
+    };
+
+    let mut extra = vec![("language".to_string(), article.language.clone())];
+    if !article.wikidata.is_empty() {
+        extra.push(("wikidata".to_string(), article.wikidata.clone()));
+    }
+
+    Capture {
+        title: article.title.clone(),
+        description,
+        tags: article.categories.clone(),
+        extra,
+        ..Capture::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_wikipedia_url() {
+        let cases = [
+            ("https://en.wikipedia.org/wiki/Rust_(programming_language)", Some(("en", "Rust_(programming_language)"))),
+            ("https://de.m.wikipedia.org/wiki/K%C3%B6ln", Some(("de", "K%C3%B6ln"))),
+            ("https://en.wikipedia.org/wiki/AC/DC", Some(("en", "AC%2FDC"))),
+            ("https://www.wikipedia.org/", None),
+            ("https://en.wikipedia.org/w/index.php?title=Rust", None),
+        ];
+        for (url, expected) in cases {
+            let expected = expected.map(|(language, title)| ArticleUrl {
+                language: language.to_string(),
+                title: title.to_string(),
+            });
+            assert_eq!(parse_url(url), expected, "url={url}");
+        }
+    }
+
+    #[test]
+    fn test_wikipedia_capture() {
+        let summary = json!({"title": "Köln", "description": "city in Germany", "extract": "Cologne is...",
+            "thumbnail": {"source": "https://upload.wikimedia.org/k.jpg"}, "wikibase_item": "Q365", "lang": "de"});
+        let categories = parse_categories(&json!({"query": {"pages": [{"categories": [
+            {"title": "Kategorie:Ort in Nordrhein-Westfalen"}, {"title": "Kategorie:Hansestadt"}]}]}}));
+        let article = parse_summary(&summary, "de", categories);
+        let capture = capture(&article);
+        assert_eq!(capture.description, "*city in Germany*\n\nCologne is...");
+        assert_eq!(capture.tags, vec!["Ort in Nordrhein-Westfalen".to_string(), "Hansestadt".to_string()]);
+        assert_eq!(
+            capture.extra,
+            vec![("language".to_string(), "de".to_string()), ("wikidata".to_string(), "Q365".to_string())]
+        );
+        assert_eq!(article.thumbnail, "https://upload.wikimedia.org/k.jpg");
+    }
+}
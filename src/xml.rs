@@ -0,0 +1,108 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+lazy_static! {
+    static ref ATTR_RE: Regex = Regex::new(r#"(?s)(?:^|\s)([^\s=]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).expect("Invalid attribute regex");
+    static ref ENTITY_RE: Regex = Regex::new(r"&(#x[0-9a-fA-F]+|#[0-9]+|amp|lt|gt|quot|apos);").expect("Invalid entity regex");
+    /// Element patterns by tag name; feeds ask for the same handful of tags once per item.
+    static ref ELEMENT_RES: Mutex<HashMap<String, Regex>> = Mutex::new(HashMap::new());
+}
+
+/// Just enough XML for the feeds this crate reads (arXiv Atom, podcast RSS):
+/// elements are matched by name, so same-named elements must not nest.
+#[derive(Debug, Clone, Copy)]
+pub struct Element<'a> {
+    attrs: &'a str,
+    pub inner: &'a str,
+}
+
+impl<'a> Element<'a> {
+    pub fn attr(&self, name: &str) -> Option<String> {
+        let caps = ATTR_RE.captures_iter(self.attrs).find(|caps| &caps[1] == name)?;
+        caps.get(2).or_else(|| caps.get(3)).map(|m| unescape(m.as_str()))
+    }
+
+    pub fn elements(&self, tag: &str) -> Vec<Element<'a>> {
+        elements(self.inner, tag)
+    }
+
+    pub fn text(&self, tag: &str) -> String {
+        text(self.inner, tag)
+    }
+}
+
+fn element_re(tag: &str) -> Regex {
+    let mut cache = ELEMENT_RES.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    cache
+        .entry(tag.to_string())
+        .or_insert_with(|| {
+            let tag = regex::escape(tag);
+            Regex::new(&format!(r"(?s)<{tag}((?:\s[^>]*?)?)(?:/>|>(.*?)</{tag}\s*>)")).expect("Invalid element regex")
+        })
+        .clone()
+}
+
+pub fn elements<'a>(xml: &'a str, tag: &str) -> Vec<Element<'a>> {
+    element_re(tag)
+        .captures_iter(xml)
+        .map(|caps| Element {
+            attrs: caps.get(1).map_or("", |m| m.as_str()),
+            inner: caps.get(2).map_or("", |m| m.as_str()),
+        })
+        .collect()
+}
+
+/// The unescaped text of the first `tag` element, or an empty string.
+pub fn text(xml: &str, tag: &str) -> String {
+    elements(xml, tag)
+        .first()
+        .map(|element| unescape(element.inner).trim().to_string())
+        .unwrap_or_default()
+}
+
+pub fn unescape(value: &str) -> String {
+    if let Some(cdata) = value.trim().strip_prefix("<![CDATA[").and_then(|v| v.strip_suffix("]]>")) {
+        return cdata.to_string();
+    }
+    ENTITY_RE.replace_all(value, |caps: &regex::Captures| {
+        let entity = &caps[1];
+        match entity {
+            "amp" => "&".to_string(),
+            "lt" => "<".to_string(),
+            "gt" => ">".to_string(),
+            "quot" => "\"".to_string(),
+            "apos" => "'".to_string(),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .unwrap_or_else(|| entity[1..].parse())
+                .ok()
+                .and_then(char::from_u32)
+                .map_or_else(|| caps[0].to_string(), String::from),
+        }
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_elements_and_text() {
+        let xml = r#"<feed><title>Query</title><entry><title>A &amp; B</title>
+            <link rel="alternate" href="https://a"/><link title='pdf' href="https://a/pdf" />
+            <arxiv:primary_category term="cs.CL"/><summary><![CDATA[<p>Hi</p>]]></summary></entry></feed>"#;
+        let entry = elements(xml, "entry")[0];
+        assert_eq!(entry.text("title"), "A & B");
+        assert_eq!(entry.text("summary"), "<p>Hi</p>");
+        let links = entry.elements("link");
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[1].attr("title").as_deref(), Some("pdf"));
+        assert_eq!(links[1].attr("href").as_deref(), Some("https://a/pdf"));
+        assert_eq!(entry.elements("arxiv:primary_category")[0].attr("term").as_deref(), Some("cs.CL"));
+        assert_eq!(unescape("&#233;&#x2014;&bogus;"), "é—&bogus;");
+    }
+}
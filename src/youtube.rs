@@ -0,0 +1,582 @@
+use eyre::{eyre, Result};
+use lazy_static::lazy_static;
+use log::{debug, error, info, warn};
+use regex::Regex;
+use scraper::{Html, Selector};
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+use crate::{
+    frontmatter_value, http, image_embed_code, new_note_path, notes, quote_yaml, sanitize_filename, vault_folder, wikilink,
+    Config, VideoMetadata,
+};
+
+lazy_static! {
+    static ref ID_RE: Regex = Regex::new(r"^[A-Za-z0-9_-]+$").expect("Invalid video id regex");
+    static ref CLIP_VIDEO_ID_RE: Regex = Regex::new(r#""videoId":"([A-Za-z0-9_-]+)""#).expect("Invalid clip regex");
+}
+
+/// Channel avatars are square; this is the size they are shown at in channel notes.
+const AVATAR_SIZE: usize = 240;
+
+const QUOTA_REASONS: [&str; 3] = ["quotaExceeded", "dailyLimitExceeded", "rateLimitExceeded"];
+
+#[derive(Debug)]
+pub struct QuotaExceeded(String);
+
+impl fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "YouTube Data API quota exceeded: {}", self.0)
+    }
+}
+
+impl std::error::Error for QuotaExceeded {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum YouTubeKind {
+    Video,
+    Shorts,
+    Live,
+    Clip,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct YouTubeUrl {
+    /// The video id, or the clip id for `YouTubeKind::Clip`.
+    pub id: String,
+    pub kind: YouTubeKind,
+}
+
+fn valid_id(id: &str) -> Option<String> {
+    ID_RE.is_match(id).then(|| id.to_string())
+}
+
+/// Parses every YouTube URL shape we've seen bookmarked: watch pages on any
+/// subdomain, youtu.be short links, embeds (including youtube-nocookie),
+/// shorts, live streams, clips and attribution links.
+pub fn parse_url(url: &str) -> Option<YouTubeUrl> {
+    debug!("parse_url: url={}", url);
+    let parsed = Url::parse(url).ok()?;
+    let host = parsed.host_str()?.to_lowercase();
+    let host = ["www.", "m.", "music."]
+        .iter()
+        .find_map(|prefix| host.strip_prefix(prefix))
+        .unwrap_or(&host)
+        .to_string();
+    let segments: Vec<&str> = parsed.path_segments()?.filter(|s| !s.is_empty()).collect();
+    let query = |key: &str| {
+        parsed
+            .query_pairs()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.into_owned())
+    };
+
+    let (id, kind) = match (host.as_str(), segments.as_slice()) {
+        ("youtu.be", [id, ..]) => (valid_id(id)?, YouTubeKind::Video),
+        ("youtube.com", ["watch", ..]) => (valid_id(&query("v")?)?, YouTubeKind::Video),
+        ("youtube.com", ["attribution_link", ..]) => {
+            return parse_url(&format!("https://www.youtube.com{}", query("u")?));
+        }
+        ("youtube.com", ["shorts", id, ..]) => (valid_id(id)?, YouTubeKind::Shorts),
+        ("youtube.com", ["live", id, ..]) => (valid_id(id)?, YouTubeKind::Live),
+        ("youtube.com", ["clip", id, ..]) => (valid_id(id)?, YouTubeKind::Clip),
+        ("youtube.com" | "youtube-nocookie.com", ["embed" | "v" | "e", id, ..]) => (valid_id(id)?, YouTubeKind::Video),
+        _ => return None,
+    };
+
+    Some(YouTubeUrl { id, kind })
+}
+
+/// Returns the video id for a YouTube URL, looking clips up on their page
+/// since a clip id doesn't identify the underlying video.
+pub async fn resolve_video_id(url: &str) -> Result<String> {
+    debug!("resolve_video_id: url={}", url);
+    let parsed = parse_url(url).ok_or_else(|| eyre!("Failed to extract video ID from URL"))?;
+    if parsed.kind != YouTubeKind::Clip {
+        return Ok(parsed.id);
+    }
+
+    let content = http::text(http::get(format!("https://www.youtube.com/clip/{}", parsed.id)).await?).await?;
+    CLIP_VIDEO_ID_RE
+        .captures(&content)
+        .and_then(|caps| caps.get(1))
+        .map(|m| m.as_str().to_string())
+        .ok_or_else(|| eyre!("Failed to resolve video ID for clip {}", parsed.id))
+}
+
+/// Uses the Data API when a key is configured, falling back to oEmbed and the
+/// watch page when there is no key or the key has run out of quota.
+pub async fn fetch_video_metadata(api_key: Option<&str>, video_id: &str) -> Result<VideoMetadata> {
+    debug!("fetch_video_metadata: video_id={}", video_id);
+    match api_key {
+        Some(api_key) => match fetch_data_api_metadata(api_key, video_id).await {
+            Err(e) if e.downcast_ref::<QuotaExceeded>().is_some() => {
+                warn!("{}; falling back to oEmbed for video_id={}", e, video_id);
+                fetch_fallback_metadata(video_id).await
+            }
+            result => result,
+        },
+        None => {
+            debug!("YOUTUBE_API_KEY not set; using oEmbed for video_id={}", video_id);
+            fetch_fallback_metadata(video_id).await
+        }
+    }
+}
+
+async fn fetch_data_api_metadata(api_key: &str, video_id: &str) -> Result<VideoMetadata> {
+    debug!("fetch_data_api_metadata: api_key={} video_id={}", api_key, video_id);
+    let url = format!(
+        "https://www.googleapis.com/youtube/v3/videos?id={video_id}&part=snippet&key={api_key}"
+    );
+
+    let response: serde_json::Value = http::json(http::get(&url).await?).await?;
+
+    if let Some(reason) = response["error"]["errors"][0]["reason"].as_str() {
+        if QUOTA_REASONS.contains(&reason) {
+            return Err(QuotaExceeded(reason.to_string()).into());
+        }
+        return Err(eyre!("YouTube Data API error for video_id={}: {}", video_id, reason));
+    }
+
+    if response["items"].as_array().unwrap_or(&Vec::new()).is_empty() {
+        return Err(eyre!("Video metadata not found for video_id={}", video_id));
+    }
+
+    let snippet = &response["items"][0]["snippet"];
+    Ok(VideoMetadata {
+        id: video_id.to_string(),
+        title: snippet["title"].as_str().unwrap_or_default().to_string(),
+        description: snippet["description"].as_str().unwrap_or_default().to_string(),
+        channel: snippet["channelTitle"].as_str().unwrap_or_default().to_string(),
+        channel_id: snippet["channelId"].as_str().unwrap_or_default().to_string(),
+        published_at: snippet["publishedAt"].as_str().unwrap_or_default().to_string(),
+        thumbnail: best_thumbnail(&snippet["thumbnails"]),
+        tags: snippet["tags"]
+            .as_array()
+            .unwrap_or(&Vec::new())
+            .iter()
+            .filter_map(|tag| tag.as_str())
+            .map(String::from)
+            .collect(),
+        channel_url: snippet["channelId"].as_str().map(channel_url).unwrap_or_default(),
+        from_data_api: true,
+    })
+}
+
+fn best_thumbnail(thumbnails: &serde_json::Value) -> String {
+    ["maxres", "standard", "high", "medium", "default"]
+        .iter()
+        .find_map(|size| thumbnails[size]["url"].as_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+async fn fetch_fallback_metadata(video_id: &str) -> Result<VideoMetadata> {
+    debug!("fetch_fallback_metadata: video_id={}", video_id);
+    let watch_url = format!("https://www.youtube.com/watch?v={video_id}");
+    let oembed_url = Url::parse_with_params("https://www.youtube.com/oembed", &[("url", watch_url.as_str()), ("format", "json")])?;
+
+    let response = http::get(oembed_url.as_str()).await?;
+    if !response.status().is_success() {
+        return Err(eyre!("oEmbed lookup failed for video_id={} with status {}", video_id, response.status()));
+    }
+    let oembed: serde_json::Value = http::json(response).await?;
+
+    let mut metadata = VideoMetadata {
+        id: video_id.to_string(),
+        title: oembed["title"].as_str().unwrap_or_default().to_string(),
+        description: String::new(),
+        channel: oembed["author_name"].as_str().unwrap_or_default().to_string(),
+        channel_id: String::new(),
+        published_at: String::new(),
+        thumbnail: oembed["thumbnail_url"].as_str().unwrap_or_default().to_string(),
+        tags: Vec::new(),
+        channel_url: oembed["author_url"].as_str().unwrap_or_default().to_string(),
+        from_data_api: false,
+    };
+
+    // The watch page only adds detail; a consent wall or layout change shouldn't fail the capture.
+    match fetch_watch_page(&watch_url).await {
+        Ok(page) => merge_watch_page(&mut metadata, page),
+        Err(e) => error!("Failed to read watch page metadata for video_id={}: {:?}", video_id, e),
+    }
+
+    Ok(metadata)
+}
+
+async fn fetch_watch_page(watch_url: &str) -> Result<VideoMetadata> {
+    let content = http::text(http::get(watch_url).await?).await?;
+    parse_watch_page(&content)
+}
+
+fn merge_watch_page(metadata: &mut VideoMetadata, page: VideoMetadata) {
+    if metadata.title.is_empty() {
+        metadata.title = page.title;
+    }
+    if metadata.channel.is_empty() {
+        metadata.channel = page.channel;
+    }
+    metadata.description = page.description;
+    if metadata.channel_url.is_empty() && !page.channel_id.is_empty() {
+        metadata.channel_url = channel_url(&page.channel_id);
+    }
+    metadata.channel_id = page.channel_id;
+    metadata.published_at = page.published_at;
+    metadata.tags = page.tags;
+    if metadata.thumbnail.is_empty() {
+        metadata.thumbnail = page.thumbnail;
+    }
+}
+
+fn meta_content(document: &Html, selector: &str) -> Result<String> {
+    let selector = Selector::parse(selector).map_err(|e| eyre!("Failed to compile selector: {}", e))?;
+    Ok(document
+        .select(&selector)
+        .next()
+        .and_then(|e| e.value().attr("content"))
+        .unwrap_or_default()
+        .to_string())
+}
+
+fn player_response(content: &str) -> Option<serde_json::Value> {
+    let start = content.find("ytInitialPlayerResponse = ")? + "ytInitialPlayerResponse = ".len();
+    serde_json::Deserializer::from_str(&content[start..])
+        .into_iter::<serde_json::Value>()
+        .next()?
+        .ok()
+}
+
+fn parse_watch_page(content: &str) -> Result<VideoMetadata> {
+    let document = Html::parse_document(content);
+
+    let mut metadata = VideoMetadata {
+        id: meta_content(&document, "meta[itemprop='identifier']")?,
+        title: meta_content(&document, "meta[name='title']")?,
+        description: meta_content(&document, "meta[name='description']")?,
+        channel: String::new(),
+        channel_id: meta_content(&document, "meta[itemprop='channelId']")?,
+        published_at: meta_content(&document, "meta[itemprop='datePublished']")?,
+        thumbnail: meta_content(&document, "meta[property='og:image']")?,
+        tags: meta_content(&document, "meta[name='keywords']")?
+            .split(',')
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect(),
+        channel_url: String::new(),
+        from_data_api: false,
+    };
+
+    // The embedded player response carries the untruncated description and keyword list.
+    if let Some(player) = player_response(content) {
+        let details = &player["videoDetails"];
+        let microformat = &player["microformat"]["playerMicroformatRenderer"];
+
+        if let Some(id) = details["videoId"].as_str() {
+            metadata.id = id.to_string();
+        }
+        if let Some(title) = details["title"].as_str() {
+            metadata.title = title.to_string();
+        }
+        if let Some(description) = details["shortDescription"].as_str() {
+            metadata.description = description.to_string();
+        }
+        if let Some(author) = details["author"].as_str() {
+            metadata.channel = author.to_string();
+        }
+        if let Some(channel_id) = details["channelId"].as_str() {
+            metadata.channel_id = channel_id.to_string();
+        }
+        if let Some(published) = microformat["publishDate"].as_str() {
+            metadata.published_at = published.to_string();
+        }
+        if let Some(keywords) = details["keywords"].as_array() {
+            metadata.tags = keywords.iter().filter_map(|tag| tag.as_str()).map(String::from).collect();
+        }
+    }
+
+    Ok(metadata)
+}
+
+#[derive(Debug)]
+pub struct ChannelMetadata {
+    pub id: String,
+    pub title: String,
+    pub url: String,
+    pub description: String,
+    pub thumbnail: String,
+}
+
+pub async fn fetch_channel_metadata(api_key: &str, channel_id: &str) -> Result<ChannelMetadata> {
+    debug!("fetch_channel_metadata: api_key={} channel_id={}", api_key, channel_id);
+    let url = format!(
+        "https://www.googleapis.com/youtube/v3/channels?id={channel_id}&part=snippet&key={api_key}"
+    );
+
+    let response: serde_json::Value = http::json(http::get(&url).await?).await?;
+
+    if response["items"].as_array().unwrap_or(&Vec::new()).is_empty() {
+        return Err(eyre!("Channel metadata not found for channel_id={}", channel_id));
+    }
+
+    let snippet = &response["items"][0]["snippet"];
+
+    Ok(ChannelMetadata {
+        id: channel_id.to_string(),
+        title: snippet["title"].as_str().unwrap_or_default().to_string(),
+        url: channel_url(channel_id),
+        description: snippet["description"].as_str().unwrap_or_default().to_string(),
+        thumbnail: best_thumbnail(&snippet["thumbnails"]),
+    })
+}
+
+/// Reads a channel page (`/@handle` or `/channel/UC...`) for when the Data API can't be used.
+async fn fetch_channel_page(url: &str) -> Result<ChannelMetadata> {
+    debug!("fetch_channel_page: url={}", url);
+    let response = http::get(url).await?;
+    if !response.status().is_success() {
+        return Err(eyre!("Channel page request to {} failed with status {}", url, response.status()));
+    }
+    parse_channel_page(&http::text(response).await?, url)
+}
+
+fn parse_channel_page(content: &str, url: &str) -> Result<ChannelMetadata> {
+    let document = Html::parse_document(content);
+    let id = match meta_content(&document, "meta[itemprop='identifier']")? {
+        id if id.is_empty() => meta_content(&document, "meta[itemprop='channelId']")?,
+        id => id,
+    };
+    Ok(ChannelMetadata {
+        url: if id.is_empty() { url.to_string() } else { channel_url(&id) },
+        id,
+        title: meta_content(&document, "meta[property='og:title']")?,
+        description: meta_content(&document, "meta[property='og:description']")?,
+        thumbnail: meta_content(&document, "meta[property='og:image']")?,
+    })
+}
+
+pub fn channel_url(channel_id: &str) -> String {
+    format!("https://www.youtube.com/channel/{channel_id}")
+}
+
+/// `avatar` is the embed for the channel thumbnail, downloaded to attachments when enabled.
+fn render_channel_note(channel: &ChannelMetadata, avatar: &str) -> String {
+    let mut note = String::new();
+    note.push_str("---\n");
+    note.push_str(&format!("channel_id: {}\n", channel.id));
+    note.push_str(&format!("title: {}\n", quote_yaml(&channel.title)));
+    note.push_str(&format!("url: {}\n", channel.url));
+    note.push_str(&format!("thumbnail: {}\n", channel.thumbnail));
+    note.push_str("type: channel\n");
+    note.push_str("---\n\n");
+    if !avatar.is_empty() {
+        note.push_str(&format!("{avatar}\n\n"));
+    }
+    note.push_str(&format!("## Description\n{}\n\n", channel.description));
+    note.push_str("## Videos\n");
+    note
+}
+
+fn append_video(note: &str, video_link: &str) -> String {
+    let entry = format!("- {video_link}");
+    if note.lines().any(|line| line.trim() == entry) {
+        return note.to_string();
+    }
+    let mut note = note.to_string();
+    if !note.ends_with('\n') {
+        note.push('\n');
+    }
+    note.push_str(&entry);
+    note.push('\n');
+    note
+}
+
+/// The channel's note: the one whose frontmatter has its `channel_id`, since display names aren't unique.
+/// Without an id the name is all there is to go on.
+fn channel_note_path(folder_path: &Path, channel_id: &str, channel: &str) -> Result<PathBuf> {
+    if channel_id.is_empty() {
+        return Ok(folder_path.join(sanitize_filename(channel)? + ".md"));
+    }
+    let existing = notes(folder_path).into_iter().find(|path| {
+        std::fs::read_to_string(path).is_ok_and(|note| frontmatter_value(&note, "channel_id").as_deref() == Some(channel_id))
+    });
+    match existing {
+        Some(path) => Ok(path),
+        None => new_note_path(folder_path, channel),
+    }
+}
+
+/// Creates the channel note on first sight and appends the video to its list.
+/// Returns the wikilink to use as the video's author, or `None` when the channel has no name to file it under.
+pub async fn update_channel_note(
+    api_key: Option<&str>,
+    metadata: &VideoMetadata,
+    video_title: &str,
+    config: &Config,
+) -> Result<Option<String>> {
+    info!(
+        "update_channel_note: channel={} channel_id={} video_title={}",
+        metadata.channel, metadata.channel_id, video_title
+    );
+    if sanitize_filename(&metadata.channel)?.trim().is_empty() {
+        warn!("update_channel_note: no channel name for video {}; skipping the channel note", video_title);
+        return Ok(None);
+    }
+    let folder_path = vault_folder(&config.vault, Some(config.channel_folder.clone()))?;
+    let file_path = channel_note_path(&folder_path, &metadata.channel_id, &metadata.channel)?;
+
+    let note = if file_path.exists() {
+        std::fs::read_to_string(&file_path)
+            .map_err(|e| eyre!("Failed to read channel note: {:?} with error {}", file_path, e))?
+    } else {
+        let minimal = || ChannelMetadata {
+            id: metadata.channel_id.clone(),
+            title: metadata.channel.clone(),
+            url: metadata.channel_url.clone(),
+            description: String::new(),
+            thumbnail: String::new(),
+        };
+        let channel = match api_key {
+            Some(api_key) if metadata.from_data_api && !metadata.channel_id.is_empty() => {
+                fetch_channel_metadata(api_key, &metadata.channel_id).await?
+            }
+            // No key, or its quota is gone: the channel page has the same basics.
+            _ if !metadata.channel_url.is_empty() => match fetch_channel_page(&metadata.channel_url).await {
+                Ok(mut channel) => {
+                    if channel.title.is_empty() {
+                        channel.title = metadata.channel.clone();
+                    }
+                    channel
+                }
+                Err(e) => {
+                    error!("Failed to read channel page {}: {:?}", metadata.channel_url, e);
+                    minimal()
+                }
+            },
+            _ => minimal(),
+        };
+        let avatar = image_embed_code(&channel.thumbnail, AVATAR_SIZE, AVATAR_SIZE, config).await;
+        render_channel_note(&channel, &avatar)
+    };
+
+    let note = append_video(&note, &wikilink(video_title)?);
+    let mut file = std::fs::File::create(&file_path)
+        .map_err(|e| eyre!("Failed to create channel note: {:?} with error {}", file_path, e))?;
+    file.write_all(note.as_bytes())
+        .map_err(|e| eyre!("Failed to write to channel note: {}", e))?;
+
+    // The file may be "Name (2).md" when another channel shares the name.
+    let stem = file_path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    Ok(Some(format!("[[{stem}]]")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_url() {
+        let cases = [
+            ("https://www.youtube.com/watch?v=y4evLICF8kk", Some(("y4evLICF8kk", YouTubeKind::Video))),
+            ("https://youtube.com/watch?v=y4evLICF8kk&t=32s", Some(("y4evLICF8kk", YouTubeKind::Video))),
+            ("https://www.youtube.com/watch?feature=share&v=y4evLICF8kk", Some(("y4evLICF8kk", YouTubeKind::Video))),
+            ("https://www.youtube.com/watch?app=desktop&list=PL123&v=y4evLICF8kk&index=2", Some(("y4evLICF8kk", YouTubeKind::Video))),
+            ("https://m.youtube.com/watch?v=y4evLICF8kk", Some(("y4evLICF8kk", YouTubeKind::Video))),
+            ("https://music.youtube.com/watch?v=y4evLICF8kk&si=abc", Some(("y4evLICF8kk", YouTubeKind::Video))),
+            ("https://youtu.be/m7lnIdudEy8?si=VE-14Y1Sk93RdA5u", Some(("m7lnIdudEy8", YouTubeKind::Video))),
+            ("https://youtu.be/m7lnIdudEy8?feature=share&t=10", Some(("m7lnIdudEy8", YouTubeKind::Video))),
+            ("http://youtu.be/EkDxsQRbIwoA", Some(("EkDxsQRbIwoA", YouTubeKind::Video))),
+            ("https://www.youtube.com/embed/y4evLICF8kk?autoplay=1", Some(("y4evLICF8kk", YouTubeKind::Video))),
+            ("https://www.youtube-nocookie.com/embed/y4evLICF8kk", Some(("y4evLICF8kk", YouTubeKind::Video))),
+            ("https://www.youtube.com/v/y4evLICF8kk", Some(("y4evLICF8kk", YouTubeKind::Video))),
+            ("https://www.youtube.com/shorts/gGrqPbb6fuM", Some(("gGrqPbb6fuM", YouTubeKind::Shorts))),
+            ("https://m.youtube.com/shorts/FjkS5rjNq-A?feature=share", Some(("FjkS5rjNq-A", YouTubeKind::Shorts))),
+            ("https://www.youtube.com/live/jfKfPfyJRdk?si=x", Some(("jfKfPfyJRdk", YouTubeKind::Live))),
+            (
+                "https://youtube.com/clip/UgkxU2HSeGL_NvmDJ-nQJrlLwllwMDBdGZFs",
+                Some(("UgkxU2HSeGL_NvmDJ-nQJrlLwllwMDBdGZFs", YouTubeKind::Clip)),
+            ),
+            (
+                "https://www.youtube.com/attribution_link?a=abc&u=/watch%3Fv%3Dy4evLICF8kk%26feature%3Dshare",
+                Some(("y4evLICF8kk", YouTubeKind::Video)),
+            ),
+            ("https://www.youtube.com/watch?list=PL123", None),
+            ("https://www.youtube.com/notshorts/gGrqPbb6fuM", None),
+            ("https://www.youtube.com/@WeeklySpiral", None),
+            ("https://www.notyoutube.com/watch?v=y4evLICF8kk", None),
+            ("not a url", None),
+        ];
+
+        for (url, expected) in cases {
+            let expected = expected.map(|(id, kind)| YouTubeUrl { id: id.to_string(), kind });
+            assert_eq!(parse_url(url), expected, "unexpected parse for {}", url);
+        }
+    }
+
+    #[test]
+    fn test_parse_watch_page() -> Result<()> {
+        let content = r#"<html><head>
+            <meta name="title" content="Meta Title">
+            <meta name="description" content="Truncated...">
+            <meta name="keywords" content="football, mills">
+            <meta itemprop="channelId" content="UCmeta">
+            <meta itemprop="datePublished" content="2021-08-16">
+            </head><body><script>var ytInitialPlayerResponse = {"videoDetails":{"videoId":"7sgCH4U7rjU","title":"Mills Concept","author":"Weekly Spiral","channelId":"UC123","shortDescription":"Full description","keywords":["football","mills concept"]},"microformat":{"playerMicroformatRenderer":{"publishDate":"2021-08-16T08:29:39-07:00"}}};var meta = {};</script></body></html>"#;
+        let metadata = parse_watch_page(content)?;
+        assert_eq!(metadata.id, "7sgCH4U7rjU");
+        assert_eq!(metadata.title, "Mills Concept");
+        assert_eq!(metadata.description, "Full description");
+        assert_eq!(metadata.channel, "Weekly Spiral");
+        assert_eq!(metadata.channel_id, "UC123");
+        assert_eq!(metadata.published_at, "2021-08-16T08:29:39-07:00");
+        assert_eq!(metadata.tags, vec!["football".to_string(), "mills concept".to_string()]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_channel_page() -> Result<()> {
+        let content = r#"<html><head>
+            <meta property="og:title" content="Weekly Spiral">
+            <meta property="og:description" content="Football breakdowns">
+            <meta property="og:image" content="https://yt3.ggpht.com/avatar.jpg">
+            <meta itemprop="identifier" content="UC123">
+            </head></html>"#;
+        let channel = parse_channel_page(content, "https://www.youtube.com/@WeeklySpiral")?;
+        assert_eq!(channel.id, "UC123");
+        assert_eq!(channel.url, "https://www.youtube.com/channel/UC123");
+        assert_eq!(channel.title, "Weekly Spiral");
+        assert_eq!(channel.thumbnail, "https://yt3.ggpht.com/avatar.jpg");
+        Ok(())
+    }
+
+    #[test]
+    fn test_append_video_is_idempotent() {
+        let channel = ChannelMetadata {
+            id: "UC123".to_string(),
+            title: "Weekly Spiral".to_string(),
+            url: channel_url("UC123"),
+            description: "Football breakdowns".to_string(),
+            thumbnail: String::new(),
+        };
+        let note = render_channel_note(&channel, "");
+        let note = append_video(&note, "[[Mills Concept]]");
+        let note = append_video(&note, "[[Mills Concept]]");
+        assert!(note.contains("url: https://www.youtube.com/channel/UC123\n"));
+        assert_eq!(note.matches("- [[Mills Concept]]").count(), 1);
+        assert!(note.ends_with("## Videos\n- [[Mills Concept]]\n"));
+    }
+
+    #[test]
+    fn test_channel_note_path_by_id() -> Result<()> {
+        let folder = std::env::temp_dir().join(format!("obsidian-bookmark-channels-{}", std::process::id()));
+        std::fs::create_dir_all(&folder)?;
+        std::fs::write(folder.join("News.md"), "---\nchannel_id: UC1\ntitle: News\n---\n")?;
+        std::fs::write(folder.join("Renamed.md"), "---\nchannel_id: UC2\ntitle: Old Name\n---\n")?;
+        assert_eq!(channel_note_path(&folder, "UC1", "News")?, folder.join("News.md"));
+        assert_eq!(channel_note_path(&folder, "UC3", "News")?, folder.join("News (2).md"));
+        assert_eq!(channel_note_path(&folder, "UC2", "New Name")?, folder.join("Renamed.md"));
+        std::fs::remove_dir_all(&folder)?;
+        Ok(())
+    }
+}